@@ -21,13 +21,14 @@ use std::cmp::Ordering;
 // The `write` function imposes strict constraints on its input.
 // * All tuples must be evenly distributed across either `n + 1` pages or `n` pages.
 // * On each page, tuples must be contiguous and ordered, starting from `1`.
-// * IDs must be ordered on all pages.
+// * IDs must be ordered on all pages. They may repeat, in which case `read`
+//   finds any of them and `lower_bound` finds the first.
 
 pub fn write<R: RelationWrite>(index: &R, elements: &[([u8; WIDTH], (u32, u16))]) -> (u32, u32, u32)
 where
     R::Page: Page<Opaque = Opaque>,
 {
-    assert!(elements.is_sorted_by(|(l, (_, _)), (r, (_, _))| l < r));
+    assert!(elements.is_sorted_by(|(l, (_, _)), (r, (_, _))| l <= r));
     let mut tape = BackwardTapeWriter::<_, AddressTokensTuple>::create(index);
     let width_1 = AddressTokensTuple::fit(tape.freespace())
         .expect("implementation: a blank page cannot fit a single tuple") as u16;
@@ -96,3 +97,53 @@ pub fn read<R: RelationRead>(
     }
     None
 }
+
+/// Returns the first token whose ID is not less than `token_id`, from which
+/// tokens can be read in order by following the tape.
+pub fn lower_bound<R: RelationRead>(
+    index: &R,
+    depth: u32,
+    start: u32,
+    token_id: [u8; WIDTH],
+) -> Option<(R::ReadGuard<'_>, u16)>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    if start == u32::MAX {
+        return None;
+    }
+    let mut id = start;
+    for _ in 0..depth {
+        let address_guard = index.read(id);
+        let address_bytes = address_guard.get(1).expect("data corruption");
+        let address_tuple = AddressTokensTuple::deserialize_ref(address_bytes);
+        let edges = address_tuple.edges();
+        let pos = edges.partition_point(|edge| edge.into_inner().0 < token_id);
+        if let Some(edge) = edges.get(pos) {
+            id = edge.into_inner().1;
+        } else {
+            return None;
+        }
+    }
+    let token_guard = index.read(id);
+    let n = token_guard.len();
+    let mut l = 1;
+    let mut r = n + 1;
+    while l < r {
+        let i = u16::midpoint(l, r);
+        let token_bytes = token_guard.get(i).expect("data corruption");
+        let key: [u8; WIDTH] = std::array::from_fn(|i| token_bytes[i]);
+        if key < token_id {
+            l = i + 1;
+        } else {
+            r = i;
+        }
+    }
+    if l <= n {
+        Some((token_guard, l))
+    } else {
+        let next = token_guard.get_opaque().next;
+        drop(token_guard);
+        (next != u32::MAX).then(|| (index.read(next), 1))
+    }
+}
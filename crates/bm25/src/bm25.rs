@@ -282,20 +282,72 @@ pub fn length_to_fieldnorm(length: u32) -> u8 {
     }
 }
 
+// Term frequencies and document lengths are stored in multiples of a unit,
+// which is a hundredth if a weight of labels is fractional, so that weighted
+// term frequencies are integers.
+pub fn unit(weights: &[f64; 4]) -> f64 {
+    if weights.iter().all(|weight| weight.fract() == 0.0) {
+        1.0
+    } else {
+        0.01
+    }
+}
+
+pub fn multipliers(weights: &[f64; 4]) -> [u32; 4] {
+    let unit = unit(weights);
+    weights.map(|weight| (weight / unit).round() as u32)
+}
+
 pub fn idf(number_of_documents: u32, token_number_of_documents: u32) -> f64 {
     let number_of_documents = number_of_documents as f64;
     let token_number_of_documents = token_number_of_documents as f64;
     ((number_of_documents + 1.0) / (token_number_of_documents + 0.5)).ln()
 }
 
-pub fn tf(fieldnorm: u8, term_frequency: u32, k1: f64, b: f64, avgdl: f64) -> f64 {
-    let term_frequency = term_frequency as f64;
-    let document_length = fieldnorm_to_length(fieldnorm) as f64;
-    (term_frequency * (k1 + 1.0)) / (term_frequency + k1 * (1.0 - b + b * document_length / avgdl))
+/// A scoring function, each of which is non-decreasing in term frequency and
+/// non-increasing in document length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scoring {
+    Bm25,
+    /// BM25 plus `delta` for each matching term, so that a matching term in a
+    /// very long document still scores more than a missing term.
+    Bm25Plus {
+        delta: f64,
+    },
+    /// BM25 on the term frequency divided by the length normalization and
+    /// shifted by `delta`, which penalizes long documents less.
+    Bm25L {
+        delta: f64,
+    },
+    /// `1 + ln(tf)` times idf, ignoring document length.
+    TfIdf,
+    /// Query likelihood with Dirichlet smoothing, where the length term is
+    /// added to each matching term and the result is clamped at zero.
+    LmDirichlet {
+        mu: f64,
+    },
+    /// Query likelihood with Jelinek-Mercer smoothing.
+    LmJelinekMercer {
+        lambda: f64,
+    },
 }
 
+impl Scoring {
+    /// Whether `Cache::limit` bounds the score of a term for any term
+    /// frequency and document length.
+    pub fn bounded(&self) -> bool {
+        matches!(
+            self,
+            Scoring::Bm25 | Scoring::Bm25Plus { .. } | Scoring::Bm25L { .. }
+        )
+    }
+}
+
+// Every scoring function is non-decreasing in term frequency and non-increasing
+// in document length, so the minimum fieldnorm and the maximum term frequency
+// bound it for any `k1`, `b` and `avgdl`, which differ between segments and
+// change as they merge.
 pub struct Wand {
-    tf: f64,
     fieldnorm: u8,
     term_frequency: u32,
 }
@@ -303,25 +355,17 @@ pub struct Wand {
 impl Wand {
     pub fn new() -> Self {
         Self {
-            tf: 0.0f64,
             fieldnorm: u8::MAX,
             term_frequency: 0_u32,
         }
     }
-    pub fn push(&mut self, fieldnorm: u8, term_frequency: u32, k1: f64, b: f64, avgdl: f64) {
-        let tf = tf(fieldnorm, term_frequency, k1, b, avgdl);
-        if self.tf < tf {
-            self.tf = tf;
-            self.fieldnorm = fieldnorm;
-            self.term_frequency = term_frequency;
-        }
+    pub fn push(&mut self, fieldnorm: u8, term_frequency: u32) {
+        self.fieldnorm = self.fieldnorm.min(fieldnorm);
+        self.term_frequency = self.term_frequency.max(term_frequency);
     }
     pub fn extend(&mut self, other: &Self) {
-        if self.tf < other.tf {
-            self.tf = other.tf;
-            self.fieldnorm = other.fieldnorm;
-            self.term_frequency = other.term_frequency;
-        }
+        self.fieldnorm = self.fieldnorm.min(other.fieldnorm);
+        self.term_frequency = self.term_frequency.max(other.term_frequency);
     }
     pub fn fieldnorm(&self) -> u8 {
         self.fieldnorm
@@ -332,28 +376,220 @@ impl Wand {
 }
 
 pub struct Cache {
+    scoring: Scoring,
+    unit: f64,
+    k1: f64,
     s0: f64,
     s1: [f64; 256],
+    s2: f64,
 }
 
 impl Cache {
     pub fn new(
         number_of_documents: u32,
         token_number_of_documents: u32,
+        token_sum_of_term_frequencies: u64,
         k1: f64,
         b: f64,
         avgdl: f64,
+        weight: f64,
+        scoring: Scoring,
+        unit: f64,
     ) -> Self {
-        Self {
-            s0: idf(number_of_documents, token_number_of_documents) * (k1 + 1.0),
-            s1: std::array::from_fn(|fieldnorm| {
-                let document_length = fieldnorm_to_length(fieldnorm as u8) as f64;
-                k1 * (1.0 - b + b * document_length / avgdl)
-            }),
+        let idf = weight * idf(number_of_documents, token_number_of_documents);
+        let avgdl = avgdl * unit;
+        let length = |fieldnorm: usize| fieldnorm_to_length(fieldnorm as u8) as f64 * unit;
+        let norm = |fieldnorm: usize| {
+            let document_length = length(fieldnorm);
+            1.0 - b + b * document_length / avgdl
+        };
+        // the probability of the term in the collection, smoothed as in Lucene
+        let p = (token_sum_of_term_frequencies as f64 * unit + 1.0)
+            / (avgdl * number_of_documents as f64 + 1.0);
+        match scoring {
+            Scoring::Bm25 | Scoring::Bm25Plus { .. } => Self {
+                scoring,
+                unit,
+                k1,
+                s0: idf * (k1 + 1.0),
+                s1: std::array::from_fn(|fieldnorm| k1 * norm(fieldnorm)),
+                s2: match scoring {
+                    Scoring::Bm25Plus { delta } => idf * delta,
+                    _ => 0.0,
+                },
+            },
+            Scoring::Bm25L { delta } => Self {
+                scoring,
+                unit,
+                k1,
+                s0: idf * (k1 + 1.0),
+                s1: std::array::from_fn(|fieldnorm| norm(fieldnorm).recip()),
+                s2: delta,
+            },
+            Scoring::TfIdf => Self {
+                scoring,
+                unit,
+                k1,
+                s0: idf,
+                s1: [0.0; 256],
+                s2: 0.0,
+            },
+            Scoring::LmDirichlet { mu } => Self {
+                scoring,
+                unit,
+                k1,
+                s0: weight,
+                s1: std::array::from_fn(|fieldnorm| {
+                    let document_length = length(fieldnorm);
+                    (mu / (document_length + mu)).ln()
+                }),
+                s2: (mu * p).recip(),
+            },
+            Scoring::LmJelinekMercer { lambda } => Self {
+                scoring,
+                unit,
+                k1,
+                s0: weight,
+                s1: std::array::from_fn(|fieldnorm| {
+                    let document_length = length(fieldnorm).max(unit);
+                    (1.0 - lambda) / (lambda * p * document_length)
+                }),
+                s2: 0.0,
+            },
         }
     }
     pub fn evaluate(&self, fieldnorm: u8, term_frequency: u32) -> f64 {
-        let term_frequency = term_frequency as f64;
-        (term_frequency * self.s0) / (term_frequency + self.s1[fieldnorm as usize])
+        if term_frequency == 0 {
+            return 0.0;
+        }
+        let term_frequency = term_frequency as f64 * self.unit;
+        match self.scoring {
+            Scoring::Bm25 | Scoring::Bm25Plus { .. } => {
+                (term_frequency * self.s0) / (term_frequency + self.s1[fieldnorm as usize])
+                    + self.s2
+            }
+            Scoring::Bm25L { .. } => {
+                let c = term_frequency * self.s1[fieldnorm as usize] + self.s2;
+                (c * self.s0) / (c + self.k1)
+            }
+            // weighted term frequencies may be fractional, below which the
+            // logarithm continues linearly
+            Scoring::TfIdf if term_frequency < 1.0 => self.s0 * term_frequency,
+            Scoring::TfIdf => self.s0 * (1.0 + term_frequency.ln()),
+            Scoring::LmDirichlet { .. } => {
+                let x = (term_frequency * self.s2).ln_1p() + self.s1[fieldnorm as usize];
+                (self.s0 * x).max(0.0)
+            }
+            Scoring::LmJelinekMercer { .. } => {
+                self.s0 * (term_frequency * self.s1[fieldnorm as usize]).ln_1p()
+            }
+        }
+    }
+    /// The scale of the score of the term, by which proximity is weighed. It is
+    /// the supremum of `evaluate` over all term frequencies for BM25 variants,
+    /// but unbounded scoring functions use the weight of the term, with idf for
+    /// TF-IDF, which bounds nothing.
+    pub fn limit(&self) -> f64 {
+        match self.scoring {
+            Scoring::Bm25Plus { .. } => self.s0 + self.s2,
+            _ => self.s0,
+        }
+    }
+}
+
+/// Positions farther apart than this do not count as proximity.
+pub const PROXIMITY_WINDOW: u32 = 5;
+
+// Each pair of terms occurring `d` positions apart contributes `1 / d^2`, as in
+// BM25TP, and the sum is saturated to `[0, 1)` like a term frequency.
+fn term_proximity(l: &[u32], r: &[u32]) -> f64 {
+    let mut sum = 0.0;
+    let mut start = 0_usize;
+    for &x in l {
+        while start < r.len() && r[start] + PROXIMITY_WINDOW < x {
+            start += 1;
+        }
+        for &y in r[start..]
+            .iter()
+            .take_while(|&&y| y <= x + PROXIMITY_WINDOW)
+        {
+            if x != y {
+                let d = x.abs_diff(y) as f64;
+                sum += 1.0 / (d * d);
+            }
+        }
+    }
+    sum / (1.0 + sum)
+}
+
+/// Scores how close the terms are, given the `limit` of each term and its
+/// positions. A pair of terms contributes at most the lesser `limit` of the
+/// two multiplied by `weight`.
+pub fn proximity(weight: f64, terms: &[(f64, &[u32])]) -> f64 {
+    let mut result = 0.0;
+    for i in 0..terms.len() {
+        for j in i + 1..terms.len() {
+            let limit = terms[i].0.min(terms[j].0);
+            result += weight * limit * term_proximity(terms[i].1, terms[j].1);
+        }
+    }
+    result
+}
+
+/// Splits the upper bound of `proximity` among the terms, so that the sum
+/// over the terms of a document bounds its proximity score.
+pub fn proximity_upper_bounds(weight: f64, limits: &[f64]) -> Vec<f64> {
+    limits
+        .iter()
+        .enumerate()
+        .map(|(i, &l)| {
+            let sum = limits
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| i != j)
+                .map(|(_, &r)| l.min(r))
+                .sum::<f64>();
+            weight * sum / 2.0
+        })
+        .collect()
+}
+
+#[test]
+fn proximity_bounded() {
+    let (a, b, c) = (
+        [1_u32, 9].as_slice(),
+        [2_u32].as_slice(),
+        [30_u32].as_slice(),
+    );
+    let terms = [(1.0, a), (2.0, b), (3.0, c)];
+    let bounds = proximity_upper_bounds(0.5, &[1.0, 2.0, 3.0]);
+    assert_eq!(bounds, vec![0.5, 0.75, 0.75]);
+    let score = proximity(0.5, &terms);
+    assert!(0.0 < score && score < bounds[0] + bounds[1]);
+    assert_eq!(proximity(0.5, &terms[1..]), 0.0);
+}
+
+#[test]
+fn scoring_monotonic() {
+    for scoring in [
+        Scoring::Bm25,
+        Scoring::Bm25Plus { delta: 1.0 },
+        Scoring::Bm25L { delta: 0.5 },
+        Scoring::TfIdf,
+        Scoring::LmDirichlet { mu: 2000.0 },
+        Scoring::LmJelinekMercer { lambda: 0.1 },
+    ] {
+        for unit in [1.0, 0.01] {
+            let cache = Cache::new(1000, 10, 30, 1.2, 0.75, 40.0, 1.0, scoring, unit);
+            assert_eq!(cache.evaluate(0, 0), 0.0);
+            for fieldnorm in 0..u8::MAX {
+                for term_frequency in 1..64 {
+                    let x = cache.evaluate(fieldnorm, term_frequency);
+                    assert!(0.0 <= x);
+                    assert!(x <= cache.evaluate(fieldnorm, term_frequency + 1));
+                    assert!(x >= cache.evaluate(fieldnorm + 1, term_frequency));
+                }
+            }
+        }
     }
 }
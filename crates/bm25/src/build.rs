@@ -13,6 +13,7 @@
 // Copyright (c) 2025-2026 TensorChord Inc.
 
 use crate::Opaque;
+use crate::dictionary::Dictionary;
 use crate::segment::{Mapping, Record, Segment};
 use crate::tape::TapeWriter;
 use crate::tuples::*;
@@ -24,6 +25,7 @@ pub fn build<R: RelationWrite, D, M>(
     index: &R,
     seed: [u8; 32],
     segment: Segment<D, M>,
+    dictionary: Dictionary,
 ) where
     R::Page: Page<Opaque = Opaque>,
     D: IntoIterator<Item = Record>,
@@ -31,31 +33,50 @@ pub fn build<R: RelationWrite, D, M>(
 {
     let k1 = bm25_options.k1;
     let b = bm25_options.b;
+    let weights = bm25_options.weights;
+    let positions = bm25_options.positions;
+    let with_dictionary = bm25_options.dictionary;
+    let scoring = bm25_options.scoring();
 
     let mut meta = TapeWriter::<_, MetaTuple>::create(index);
     assert_eq!(meta.first(), 0);
 
-    let flushed = crate::flush::flush(bm25_options.k1, bm25_options.b, index, segment);
+    let mut records = segment.records.into_iter().peekable();
+    let flushed = if records.peek().is_some() {
+        let segment = Segment {
+            records,
+            mappings: segment.mappings,
+            positions: segment.positions,
+        };
+        Some(crate::flush::flush(index, segment))
+    } else {
+        None
+    };
+
+    let mut tape_segments = TapeWriter::<_, SegmentTuple>::create(index);
+    if let Some(flushed) = flushed {
+        tape_segments.push(flushed);
+    }
 
     let tape_vectors = TapeWriter::<_, VectorTuple>::create(index);
 
+    let [
+        (ptr_terms, depth_terms, start_terms, free_terms),
+        (ptr_strings, depth_strings, start_strings, free_strings),
+    ] = dictionary.write(index);
+
     let mut tape_jump = TapeWriter::<_, JumpTuple>::create(index);
     let ptr_jump = tape_jump.push(JumpTuple {
         ptr_vectors: { tape_vectors }.first(),
-        number_of_documents: flushed.number_of_documents,
-        sum_of_document_lengths: flushed.sum_of_document_lengths,
-        width_1_documents: flushed.width_1_documents,
-        width_0_documents: flushed.width_0_documents,
-        depth_documents: flushed.depth_documents,
-        start_documents: flushed.start_documents,
-        free_documents: flushed.free_documents,
-        depth_tokens: flushed.depth_tokens,
-        start_tokens: flushed.start_tokens,
-        free_tokens: flushed.free_tokens,
-        ptr_documents: flushed.ptr_documents,
-        ptr_tokens: flushed.ptr_tokens,
-        ptr_summaries: flushed.ptr_summaries,
-        ptr_blocks: flushed.ptr_blocks,
+        ptr_segments: { tape_segments }.first(),
+        ptr_terms,
+        depth_terms,
+        start_terms,
+        free_terms,
+        ptr_strings,
+        depth_strings,
+        start_strings,
+        free_strings,
     });
     assert_eq!(ptr_jump.1, 1);
 
@@ -67,5 +88,9 @@ pub fn build<R: RelationWrite, D, M>(
         ptr_lock: { tape_lock }.first(),
         ptr_jump: ptr_jump.0,
         seed,
+        weights,
+        positions,
+        scoring,
+        dictionary: with_dictionary,
     });
 }
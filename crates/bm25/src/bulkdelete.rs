@@ -76,8 +76,25 @@ pub fn bulkdelete<R: RelationRead + RelationWrite>(
         }
     }
 
+    let mut segments = Vec::new();
     {
-        let first = jump_tuple.ptr_documents();
+        let first = jump_tuple.ptr_segments();
+        assert!(first != u32::MAX);
+        let mut current = first;
+        while current != u32::MAX {
+            let guard = index.read(current);
+            for i in 1..=guard.len() {
+                let bytes = guard.get(i).expect("data corruption");
+                let tuple = SegmentTuple::deserialize_ref(bytes);
+                segments.push(((current, i), tuple.ptr_documents()));
+            }
+            current = guard.get_opaque().next;
+        }
+    }
+
+    for (ptr_segment, ptr_documents) in segments {
+        let mut number_of_deleted_documents = 0_u32;
+        let first = ptr_documents;
         assert!(first != u32::MAX);
         let mut current = first;
         while current != u32::MAX {
@@ -101,6 +118,7 @@ pub fn bulkdelete<R: RelationRead + RelationWrite>(
                     let mut tuple = DocumentTuple::deserialize_mut(bytes);
                     if !bool::from(*tuple.deleted()) && callback(*tuple.payload()) {
                         *tuple.deleted() = Bool::TRUE;
+                        number_of_deleted_documents += 1;
                     }
                 }
                 current = write.get_opaque().next;
@@ -108,5 +126,11 @@ pub fn bulkdelete<R: RelationRead + RelationWrite>(
                 current = read.get_opaque().next;
             }
         }
+        if number_of_deleted_documents != 0 {
+            let mut guard = index.write(ptr_segment.0);
+            let bytes = guard.get_mut(ptr_segment.1).expect("data corruption");
+            let mut tuple = SegmentTuple::deserialize_mut(bytes);
+            *tuple.number_of_deleted_documents() += number_of_deleted_documents;
+        }
     }
 }
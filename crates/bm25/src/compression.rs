@@ -134,3 +134,47 @@ pub fn decompress_term_frequencies(
         decompressed.set_len(new_len as u8);
     }
 }
+
+/// Appends the number of positions and the gaps between them, each as a
+/// variable-length integer.
+pub fn compress_positions(uncompressed: &[u32], compressed: &mut Vec<u8>) {
+    debug_assert!(uncompressed.is_sorted());
+    fn put(compressed: &mut Vec<u8>, mut x: u32) {
+        while x >= 0x80 {
+            compressed.push((x as u8) | 0x80);
+            x >>= 7;
+        }
+        compressed.push(x as u8);
+    }
+    put(compressed, uncompressed.len() as u32);
+    let mut last = 0_u32;
+    for &position in uncompressed {
+        put(compressed, position - last);
+        last = position;
+    }
+}
+
+/// Reads positions written by `compress_positions` and advances `compressed`.
+pub fn decompress_positions(compressed: &mut &[u8]) -> Vec<u32> {
+    fn get(compressed: &mut &[u8]) -> u32 {
+        let mut x = 0_u32;
+        let mut shift = 0_u32;
+        loop {
+            let (&byte, rest) = compressed.split_first().expect("data corruption");
+            *compressed = rest;
+            x |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return x;
+            }
+            shift += 7;
+        }
+    }
+    let n = get(compressed) as usize;
+    let mut decompressed = Vec::with_capacity(n);
+    let mut last = 0_u32;
+    for _ in 0..n {
+        last += get(compressed);
+        decompressed.push(last);
+    }
+    decompressed
+}
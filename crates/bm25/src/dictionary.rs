@@ -0,0 +1,816 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::tape::{TapeReader, TapeWriter};
+use crate::tuples::*;
+use crate::vector::{Document, Element, Query, intern, is_hashed};
+use crate::{Opaque, WIDTH};
+use index::relation::{Page, RelationRead, RelationWrite};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+// Keys of terms longer than `WIDTH` bytes are hashes, so the dictionary keeps
+// their strings. Documents carry the strings of their hashed keys, which are
+// added to the dictionary when documents are sealed. A document whose string
+// shares a key with a string of the dictionary is rejected on insertion, and
+// documents colliding with each other before they are sealed are rejected
+// when they are sealed, so readers keep the first string of a key and never
+// fail on a collision.
+//
+// The dictionary is written twice, each time as a tape with an address tree
+// written by `address_tokens`, which only needs tuples to begin with what they
+// are ordered by. Terms are in the order of keys, so that the string of a key
+// is looked up, and strings are in the order of their bytes, led by their
+// first `WIDTH` bytes, so that strings are found by their prefixes.
+
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    terms: BTreeMap<[u8; WIDTH], Vec<u8>>,
+}
+
+impl Dictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn read<R: RelationRead>(index: &R, ptr_terms: u32) -> Self
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        let mut tape = TapeReader::new(ptr_terms, |bytes| {
+            let term_tuple = TermTuple::deserialize_ref(bytes);
+            (term_tuple.key(), term_tuple.term().to_vec())
+        });
+        let mut result = Self::new();
+        while let Some((key, term)) = tape.next(index) {
+            result.terms.insert(key, term);
+        }
+        result
+    }
+    /// Writes the tapes of terms and strings with their address trees,
+    /// returning for each the first page of the tape and the depth, the root
+    /// and the first page of the tree.
+    pub fn write<R: RelationWrite>(&self, index: &R) -> [(u32, u32, u32, u32); 2]
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        let mut tape = TapeWriter::<_, TermTuple>::create(index);
+        let mut map_terms = Vec::with_capacity(self.terms.len());
+        for (key, term) in self.terms.iter() {
+            let location = tape.push(TermTuple {
+                key: *key,
+                term: term.clone(),
+            });
+            map_terms.push((*key, location));
+        }
+        let ptr_terms = tape.first();
+        drop(tape);
+        let (depth_terms, start_terms, free_terms) =
+            crate::address_tokens::write(index, &map_terms);
+
+        let mut strings = self.terms.iter().collect::<Vec<_>>();
+        strings.sort_by_key(|&(_, term)| term);
+        let mut tape = TapeWriter::<_, StringTuple>::create(index);
+        let mut map_strings = Vec::with_capacity(strings.len());
+        for (key, term) in strings {
+            let prefix = prefix(term);
+            let location = tape.push(StringTuple {
+                prefix,
+                key: *key,
+                term: term.clone(),
+            });
+            map_strings.push((prefix, location));
+        }
+        let ptr_strings = tape.first();
+        drop(tape);
+        let (depth_strings, start_strings, free_strings) =
+            crate::address_tokens::write(index, &map_strings);
+
+        [
+            (ptr_terms, depth_terms, start_terms, free_terms),
+            (ptr_strings, depth_strings, start_strings, free_strings),
+        ]
+    }
+    /// Adds the string of a hashed key, keeping the existing string if the
+    /// key already has a different one.
+    pub fn insert(&mut self, key: [u8; WIDTH], term: &[u8]) -> Result<(), Collision> {
+        debug_assert!(is_hashed(&key));
+        if let Some(existing) = self.terms.get(&key) {
+            if existing != term {
+                return Err(Collision::new(existing, term));
+            }
+        } else {
+            self.terms.insert(key, term.to_vec());
+        }
+        Ok(())
+    }
+    pub fn get(&self, key: &[u8; WIDTH]) -> Option<&[u8]> {
+        self.terms.get(key).map(Vec::as_slice)
+    }
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = ([u8; WIDTH], &[u8])> {
+        self.terms.iter().map(|(key, term)| (*key, term.as_slice()))
+    }
+}
+
+/// Two strings of terms sharing a hashed key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    existing: Vec<u8>,
+    term: Vec<u8>,
+}
+
+impl Collision {
+    fn new(existing: &[u8], term: &[u8]) -> Self {
+        Self {
+            existing: existing.to_vec(),
+            term: term.to_vec(),
+        }
+    }
+}
+
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "hash collision between terms {:?} and {:?}; \
+            please use REINDEX to rebuild the index with another seed.",
+            String::from_utf8_lossy(&self.existing),
+            String::from_utf8_lossy(&self.term),
+        )
+    }
+}
+
+/// Encodes the strings of hashed keys of a document, in the order of keys.
+pub fn encode(elements: &[Element], terms: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (Element { key, .. }, term) in elements.iter().zip(terms) {
+        if is_hashed(key) {
+            bytes.extend((term.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(term);
+        }
+    }
+    bytes
+}
+
+/// Decodes the strings of hashed keys of a document into the dictionary,
+/// keeping the first string of a key and returning the first collision.
+pub fn decode(
+    elements: &[Element],
+    mut bytes: &[u8],
+    dictionary: &mut Dictionary,
+) -> Result<(), Collision> {
+    let mut result = Ok(());
+    for Element { key, .. } in elements {
+        if is_hashed(key) {
+            let (len, rest) = bytes.split_first_chunk::<4>().expect("data corruption");
+            let len = u32::from_le_bytes(*len) as usize;
+            assert!(rest.len() >= len, "data corruption");
+            let (term, rest) = rest.split_at(len);
+            if let Err(collision) = dictionary.insert(*key, term)
+                && result.is_ok()
+            {
+                result = Err(collision);
+            }
+            bytes = rest;
+        }
+    }
+    assert!(bytes.is_empty(), "data corruption");
+    result
+}
+
+/// Returns the keys of the index in order, each with its string, which is
+/// unknown for a hashed key if the index has no dictionary, and its document
+/// frequency, which counts documents of segments as scoring does and live
+/// documents that are not sealed yet.
+pub fn vocabulary<R: RelationRead>(index: &R) -> Vec<([u8; WIDTH], Option<Vec<u8>>, u32)>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    let ptr_jump = meta_tuple.ptr_jump();
+    let dictionary = meta_tuple.dictionary();
+    drop(meta_guard);
+
+    let jump_guard = index.read(ptr_jump);
+    let jump_bytes = jump_guard.get(1).expect("data corruption");
+    let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
+
+    let mut terms = dictionary.then(|| Dictionary::read(index, jump_tuple.ptr_terms()));
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+    let mut counts = sealed(index, &segments);
+    for (key, number_of_documents) in unsealed(index, jump_tuple.ptr_vectors(), terms.as_mut()) {
+        *counts.entry(key).or_default() += number_of_documents;
+    }
+
+    counts
+        .into_iter()
+        .filter(|&(_, number_of_documents)| number_of_documents != 0)
+        .map(|(key, number_of_documents)| {
+            let term = if is_hashed(&key) {
+                terms
+                    .as_ref()
+                    .and_then(|terms| terms.get(&key))
+                    .map(<[u8]>::to_vec)
+            } else {
+                Some(verbatim(&key))
+            };
+            (key, term, number_of_documents)
+        })
+        .collect()
+}
+
+/// Expands each prefix of the query to the terms of the index beginning with
+/// it, keeping the `max_expansions` terms with the most documents, and each
+/// fuzzy term to the terms of the index within its fuzziness, keeping the
+/// `max_expansions` closest terms. Hashed terms are only found if the index
+/// has a dictionary.
+pub fn expand<R: RelationRead>(index: &R, query: Query) -> Query
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    if query.prefixes().is_empty() && query.fuzziness() == 0 {
+        return query;
+    }
+
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    let ptr_jump = meta_tuple.ptr_jump();
+    let dictionary = meta_tuple.dictionary();
+    let seed = meta_tuple.seed();
+    drop(meta_guard);
+
+    let jump_guard = index.read(ptr_jump);
+    let jump_bytes = jump_guard.get(1).expect("data corruption");
+    let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
+
+    // strings of documents that are not sealed yet, which are not in the
+    // dictionary if their keys are not in any segment
+    let mut pending = dictionary.then(Dictionary::new);
+    let strings = (jump_tuple.depth_strings(), jump_tuple.start_strings());
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+    let unsealed = unsealed(index, jump_tuple.ptr_vectors(), pending.as_mut());
+
+    let mut expansions = Vec::new();
+    for prefix in query.prefixes() {
+        let mut counts = BTreeMap::<[u8; WIDTH], (Vec<u8>, u32)>::new();
+        // keys of short terms are the terms themselves, so they are in order
+        if prefix.len() < WIDTH && !prefix.contains(&0) {
+            for segment in segments.iter() {
+                let tree = (segment.depth_tokens, segment.start_tokens);
+                prefixed(
+                    index,
+                    tree,
+                    prefix,
+                    tokens,
+                    |key, term, number_of_documents| {
+                        counts.entry(key).or_insert_with(|| (term, 0)).1 += number_of_documents;
+                    },
+                );
+            }
+        }
+        if let Some(pending) = pending.as_ref() {
+            prefixed(index, strings, prefix, self::strings, |key, term, _| {
+                let number_of_documents = sealed_document_frequency(index, &segments, key);
+                counts.insert(key, (term, number_of_documents));
+            });
+            for (key, term) in pending.iter() {
+                if term.starts_with(prefix) {
+                    counts.entry(key).or_insert_with(|| (term.to_vec(), 0));
+                }
+            }
+        }
+        for (&key, &number_of_documents) in unsealed.iter() {
+            if let Some(entry) = counts.get_mut(&key) {
+                entry.1 += number_of_documents;
+            } else if !is_hashed(&key) && verbatim(&key).starts_with(prefix) {
+                counts.insert(key, (verbatim(&key), number_of_documents));
+            }
+        }
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort_by_key(|&(key, (_, number_of_documents))| (Reverse(number_of_documents), key));
+        counts.truncate(query.max_expansions());
+        expansions.extend(counts.into_iter().map(|(key, (term, _))| (key, term, 1.0)));
+    }
+
+    if query.fuzziness() != 0 {
+        let k = query.fuzziness() as usize;
+        for term in query.fuzzy() {
+            let key = intern(&seed, term);
+            let Some(i) = query.iter().position(|&k| k == key) else {
+                continue;
+            };
+            let weight = query.weights()[i];
+            let chars = String::from_utf8_lossy(term).chars().collect::<Vec<_>>();
+            let mut counts = BTreeMap::<[u8; WIDTH], (Vec<u8>, usize, u32)>::new();
+            for segment in segments.iter() {
+                let tree = (segment.depth_tokens, segment.start_tokens);
+                fuzzy(
+                    index,
+                    tree,
+                    &chars,
+                    k,
+                    tokens,
+                    |key, term, d, number_of_documents| {
+                        counts.entry(key).or_insert((term, d, 0)).2 += number_of_documents;
+                    },
+                );
+            }
+            if let Some(pending) = pending.as_ref() {
+                fuzzy(
+                    index,
+                    strings,
+                    &chars,
+                    k,
+                    self::strings,
+                    |key, term, d, _| {
+                        let number_of_documents = sealed_document_frequency(index, &segments, key);
+                        counts.insert(key, (term, d, number_of_documents));
+                    },
+                );
+                for (key, term) in pending.iter() {
+                    let other = String::from_utf8_lossy(term).chars().collect::<Vec<_>>();
+                    if let Ok(d) = distance(&chars, &other, k) {
+                        counts.entry(key).or_insert((term.to_vec(), d, 0));
+                    }
+                }
+            }
+            for (&key, &number_of_documents) in unsealed.iter() {
+                if let Some(entry) = counts.get_mut(&key) {
+                    entry.2 += number_of_documents;
+                } else if !is_hashed(&key) {
+                    let term = verbatim(&key);
+                    let other = String::from_utf8_lossy(&term).chars().collect::<Vec<_>>();
+                    if let Ok(d) = distance(&chars, &other, k) {
+                        counts.insert(key, (term, d, number_of_documents));
+                    }
+                }
+            }
+            let mut candidates = counts
+                .into_iter()
+                .filter(|&(k, (_, _, number_of_documents))| k != key && number_of_documents != 0)
+                .map(|(k, (term, d, number_of_documents))| {
+                    (d, Reverse(number_of_documents), k, term)
+                })
+                .collect::<Vec<_>>();
+            candidates.sort_unstable();
+            candidates.truncate(query.max_expansions());
+            for (d, _, k, term) in candidates {
+                let weight = weight * query.fuzzy_discount().powi(d as i32);
+                expansions.push((k, term, weight));
+            }
+        }
+    }
+
+    query.with_expansions(expansions)
+}
+
+/// Reads a token of a segment as a key that is not hashed with its string and
+/// document frequency.
+fn tokens(bytes: &[u8]) -> Option<([u8; WIDTH], Vec<u8>, u32)> {
+    let token_tuple = TokenTuple::deserialize_ref(bytes);
+    let key = token_tuple.id();
+    (!is_hashed(&key)).then(|| (key, verbatim(&key), token_tuple.number_of_documents()))
+}
+
+/// Reads a string of the dictionary with its key.
+fn strings(bytes: &[u8]) -> Option<([u8; WIDTH], Vec<u8>, u32)> {
+    let string_tuple = StringTuple::deserialize_ref(bytes);
+    Some((string_tuple.key(), string_tuple.term().to_vec(), 0))
+}
+
+/// Sums document frequencies of a key in segments.
+fn sealed_document_frequency<R: RelationRead>(
+    index: &R,
+    segments: &[SegmentTuple],
+    key: [u8; WIDTH],
+) -> u32
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut result = 0_u32;
+    for segment in segments.iter() {
+        if let Some((token_guard, token_i)) =
+            crate::address_tokens::read(index, segment.depth_tokens, segment.start_tokens, key)
+        {
+            let token_bytes = token_guard.get(token_i).expect("data corruption");
+            let token_tuple = TokenTuple::deserialize_ref(token_bytes);
+            result += token_tuple.number_of_documents();
+        }
+    }
+    result
+}
+
+/// Calls `f` with each string of a tape with an address tree that begins with
+/// the prefix, where tuples are ordered by the first `WIDTH` bytes of their
+/// strings and `entry` reads the key, string and count of a tuple, if any.
+fn prefixed<R: RelationRead>(
+    index: &R,
+    (depth, start): (u32, u32),
+    prefix: &[u8],
+    entry: impl Fn(&[u8]) -> Option<([u8; WIDTH], Vec<u8>, u32)>,
+    mut f: impl FnMut([u8; WIDTH], Vec<u8>, u32),
+) where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let head = &prefix[..prefix.len().min(WIDTH)];
+    let mut cursor = crate::address_tokens::lower_bound(index, depth, start, self::prefix(head));
+    'scan: while let Some((guard, first)) = cursor {
+        for i in first..=guard.len() {
+            let bytes = guard.get(i).expect("data corruption");
+            if !bytes[..WIDTH].starts_with(head) {
+                break 'scan;
+            }
+            if let Some((key, term, count)) = entry(bytes)
+                && term.starts_with(prefix)
+            {
+                f(key, term, count);
+            }
+        }
+        let next = guard.get_opaque().next;
+        drop(guard);
+        cursor = (next != u32::MAX).then(|| (index.read(next), 1));
+    }
+}
+
+/// Calls `f` with each string of a tape with an address tree that is within
+/// `k` of the string, with its distance, where tuples are ordered by the first
+/// `WIDTH` bytes of their strings and `entry` reads the key, string and count
+/// of a tuple, if any. Strings are walked in order, and strings beginning with
+/// a prefix that is too far from the string are skipped by seeking past them
+/// if the prefix is at most `WIDTH` bytes.
+fn fuzzy<R: RelationRead>(
+    index: &R,
+    (depth, start): (u32, u32),
+    chars: &[char],
+    k: usize,
+    entry: impl Fn(&[u8]) -> Option<([u8; WIDTH], Vec<u8>, u32)>,
+    mut f: impl FnMut([u8; WIDTH], Vec<u8>, usize, u32),
+) where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut lower = Some([0_u8; WIDTH]);
+    while let Some(key) = lower.take() {
+        let mut cursor = crate::address_tokens::lower_bound(index, depth, start, key);
+        'scan: while let Some((guard, first)) = cursor {
+            for i in first..=guard.len() {
+                let bytes = guard.get(i).expect("data corruption");
+                let Some((key, term, count)) = entry(bytes) else {
+                    continue;
+                };
+                let other = String::from_utf8_lossy(&term);
+                let other_chars = other.chars().collect::<Vec<_>>();
+                match distance(chars, &other_chars, k) {
+                    Ok(d) => f(key, term, d, count),
+                    // seeking needs the prefix in bytes, which is only known
+                    // for a valid string
+                    Err(Some(n)) if matches!(other, Cow::Borrowed(_)) => {
+                        let len = other.char_indices().nth(n).map_or(term.len(), |(i, _)| i);
+                        if len <= WIDTH {
+                            lower = successor(&term[..len]);
+                            break 'scan;
+                        }
+                    }
+                    Err(_) => {}
+                }
+            }
+            let next = guard.get_opaque().next;
+            drop(guard);
+            cursor = (next != u32::MAX).then(|| (index.read(next), 1));
+        }
+    }
+}
+
+/// Returns the least key greater than every key beginning with the prefix.
+fn successor(prefix: &[u8]) -> Option<[u8; WIDTH]> {
+    let mut result = [0_u8; WIDTH];
+    result[..prefix.len()].copy_from_slice(prefix);
+    for i in (0..prefix.len()).rev() {
+        if result[i] != u8::MAX {
+            result[i] += 1;
+            return Some(result);
+        }
+        result[i] = 0;
+    }
+    None
+}
+
+/// Returns the Levenshtein distance between two strings if it is at most
+/// `k`. Otherwise, returns the length of the shortest prefix of `b` such that
+/// no string beginning with it is within `k` of `a`, if there is one.
+fn distance(a: &[char], b: &[char], k: usize) -> Result<usize, Option<usize>> {
+    // `row[i]` is the distance between `a[..i]` and the prefix of `b`
+    let mut row = (0..=a.len()).collect::<Vec<_>>();
+    for (j, &y) in b.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = j + 1;
+        let mut min = row[0];
+        for (i, &x) in a.iter().enumerate() {
+            let value = (row[i + 1] + 1)
+                .min(row[i] + 1)
+                .min(diagonal + (x != y) as usize);
+            diagonal = row[i + 1];
+            row[i + 1] = value;
+            min = min.min(value);
+        }
+        if min > k {
+            return Err(Some(j + 1));
+        }
+    }
+    Some(row[a.len()]).filter(|&d| d <= k).ok_or(None)
+}
+
+/// Returns the first `WIDTH` bytes of a string, padded with zeros, which are
+/// in the order of strings.
+pub fn prefix(term: &[u8]) -> [u8; WIDTH] {
+    let mut result = [0_u8; WIDTH];
+    let len = term.len().min(WIDTH);
+    result[..len].copy_from_slice(&term[..len]);
+    result
+}
+
+fn verbatim(key: &[u8; WIDTH]) -> Vec<u8> {
+    let len = key.iter().position(|&x| x == 0).unwrap_or(WIDTH);
+    key[..len].to_vec()
+}
+
+/// Sums document frequencies of the keys of segments.
+fn sealed<R: RelationRead>(index: &R, segments: &[SegmentTuple]) -> BTreeMap<[u8; WIDTH], u32>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut counts = BTreeMap::<[u8; WIDTH], u32>::new();
+    for segment in segments {
+        let mut tape_tokens = TapeReader::new(segment.ptr_tokens, |bytes| {
+            let token_tuple = TokenTuple::deserialize_ref(bytes);
+            (token_tuple.id(), token_tuple.number_of_documents())
+        });
+        while let Some((key, number_of_documents)) = tape_tokens.next(index) {
+            *counts.entry(key).or_default() += number_of_documents;
+        }
+    }
+    counts
+}
+
+/// Checks the strings of hashed keys of a document against each other and
+/// against the dictionary, looking up each key in its address tree.
+pub fn check<R: RelationRead>(
+    index: &R,
+    depth_terms: u32,
+    start_terms: u32,
+    document: &Document,
+) -> Result<(), Collision>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let terms = document.terms().expect("terms are missing");
+    let mut own = Dictionary::new();
+    for (Element { key, .. }, term) in document.iter().zip(terms) {
+        if is_hashed(key) {
+            own.insert(*key, term)?;
+        }
+    }
+    for (key, term) in own.iter() {
+        if let Some((term_guard, term_i)) =
+            crate::address_tokens::read(index, depth_terms, start_terms, key)
+        {
+            let term_bytes = term_guard.get(term_i).expect("data corruption");
+            let term_tuple = TermTuple::deserialize_ref(term_bytes);
+            if term_tuple.term() != term {
+                return Err(Collision::new(term_tuple.term(), term));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Counts live documents that are not sealed yet by their keys, adding the
+/// strings of their hashed keys to the dictionary.
+fn unsealed<R: RelationRead>(
+    index: &R,
+    ptr_vectors: u32,
+    mut terms: Option<&mut Dictionary>,
+) -> BTreeMap<[u8; WIDTH], u32>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut counts = BTreeMap::<[u8; WIDTH], u32>::new();
+    crate::vectors::read(index, ptr_vectors, |document| {
+        if let Some(terms) = terms.as_deref_mut() {
+            // colliding documents are rejected when they are sealed
+            let _ = decode(document.elements, document.strings, terms);
+        }
+        for Element { key, .. } in document.elements {
+            *counts.entry(*key).or_default() += 1;
+        }
+    });
+    counts
+}
+
+#[test]
+fn distance_bounded() {
+    let chars = |s: &str| s.chars().collect::<Vec<_>>();
+    assert_eq!(distance(&chars("postgres"), &chars("postgres"), 2), Ok(0));
+    assert_eq!(distance(&chars("postgres"), &chars("postgers"), 2), Ok(2));
+    assert_eq!(distance(&chars("postgres"), &chars("postgre"), 1), Ok(1));
+    // every string beginning with "progr" is at least 3 away from "postgres"
+    assert_eq!(
+        distance(&chars("postgres"), &chars("progress"), 2),
+        Err(Some(5))
+    );
+    assert_eq!(distance(&chars("postgres"), &chars("post"), 1), Err(None));
+    assert_eq!(distance(&chars("bm25"), &chars("bm"), 1), Err(None));
+    assert_eq!(distance(&chars("über"), &chars("uber"), 1), Ok(1));
+    // every string beginning with "xyz" is at least 3 away from "bm25"
+    assert_eq!(distance(&chars("bm25"), &chars("xyzzy"), 2), Err(Some(3)));
+    assert_eq!(
+        distance(&chars("bm25"), &chars("bm25bm25"), 2),
+        Err(Some(7))
+    );
+}
+
+#[test]
+fn fuzzy_seeks() {
+    use crate::testing::*;
+    use crate::types::Bm25IndexOptions;
+    assert_eq!(successor(b"ab"), Some(*b"ac\0\0\0\0\0\0\0\0\0\0\0\0\0\0"));
+    assert_eq!(
+        successor(&[b'a', u8::MAX]).map(|x| x[..2].to_vec()),
+        Some(b"b\0".to_vec())
+    );
+    assert_eq!(successor(&[u8::MAX]), None);
+    let words = [
+        "database",
+        "databases",
+        "databse",
+        "datum",
+        "postgres",
+        "postgresql",
+        "progress",
+        "search",
+        "xylophone",
+        "zebra",
+    ];
+    let corpus = words
+        .iter()
+        .map(|&word| document(&[(word, 1)]))
+        .collect::<Vec<_>>();
+    let index = build(Bm25IndexOptions::default(), &corpus);
+    let segment = &segments(&index)[0];
+    for (word, k) in [
+        ("database", 1),
+        ("postgrsql", 2),
+        ("zebras", 1),
+        ("qqqq", 2),
+    ] {
+        let chars = word.chars().collect::<Vec<_>>();
+        let mut found = Vec::new();
+        fuzzy(
+            &index,
+            (segment.depth_tokens, segment.start_tokens),
+            &chars,
+            k,
+            tokens,
+            |_, term, d, number_of_documents| {
+                found.push((String::from_utf8(term).unwrap(), d, number_of_documents));
+            },
+        );
+        let expected = words
+            .iter()
+            .filter_map(|&other| {
+                let d = distance(&chars, &other.chars().collect::<Vec<_>>(), k).ok()?;
+                Some((other.to_string(), d, 1))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(found, expected, "{word}");
+    }
+}
+
+#[test]
+fn expand_seeks_strings() {
+    use crate::testing::*;
+    use crate::types::Bm25IndexOptions;
+    let options = Bm25IndexOptions {
+        dictionary: true,
+        ..Default::default()
+    };
+    let word = |i: usize| format!("internationalization_{i:04}");
+    let corpus = (0..2000)
+        .map(|i| document(&[(&word(i), 1), ("inter", 1)]))
+        .collect::<Vec<_>>();
+    let index = build(options, &corpus);
+    // strings of documents that are not sealed yet are found too
+    let pending = document(&[(&word(9999), 1)]);
+    crate::insert::insert(&index, &pending, payload(2000)).unwrap();
+    let expanded = |query: Query| {
+        let mut result = expand(&index, query)
+            .expansions()
+            .iter()
+            .map(|(_, term)| String::from_utf8(term.clone()).unwrap())
+            .collect::<Vec<_>>();
+        result.sort();
+        result
+    };
+    let query =
+        Query::new(Vec::new()).with_prefixes(vec![b"internationalization_01".to_vec()], 1000);
+    assert_eq!(expanded(query), (100..200).map(word).collect::<Vec<_>>());
+    let query =
+        Query::new(Vec::new()).with_prefixes(vec![b"internationalization_99".to_vec()], 1000);
+    assert_eq!(expanded(query), vec![word(9999)]);
+    let term = b"internationalization_0123x";
+    let query = Query::new(vec![intern(&SEED, term)])
+        .with_prefixes(Vec::new(), 1000)
+        .with_fuzziness(vec![term.to_vec()], 1, 0.5);
+    assert_eq!(expanded(query), vec![word(123)]);
+    let problems = |index| {
+        crate::verify::verify(index, None)
+            .into_iter()
+            .map(|problem| problem.message)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(problems(&index), Vec::<String>::new());
+    maintain(&index).unwrap();
+    assert_eq!(problems(&index), Vec::<String>::new());
+}
+
+#[test]
+fn insert_collision() {
+    use crate::testing::SEED;
+    let key = intern(&SEED, b"internationalization");
+    let mut dictionary = Dictionary::new();
+    assert_eq!(dictionary.insert(key, b"internationalization"), Ok(()));
+    assert_eq!(dictionary.insert(key, b"internationalization"), Ok(()));
+    let collision = dictionary.insert(key, b"forged").unwrap_err();
+    assert!(
+        collision
+            .to_string()
+            .contains("\"internationalization\" and \"forged\"")
+    );
+    assert_eq!(
+        dictionary.get(&key),
+        Some(b"internationalization".as_slice())
+    );
+}
+
+#[test]
+fn insert_rejects_collision() {
+    use crate::testing::*;
+    use crate::types::Bm25IndexOptions;
+    let options = Bm25IndexOptions {
+        dictionary: true,
+        ..Default::default()
+    };
+    let index = build(options, &[document(&[("internationalization", 1)])]);
+    let forge = |term: &str, forged: &str| {
+        let key = intern(&SEED, term.as_bytes());
+        Document::new(vec![Element { key, value: 1 }]).with_terms(vec![forged.as_bytes().to_vec()])
+    };
+    // against the dictionary
+    let forged = forge("internationalization", "forged");
+    assert!(crate::insert::insert(&index, &forged, payload(1)).is_err());
+    // against documents that are not sealed yet, when they are sealed
+    let sound = document(&[
+        ("internationalization", 1),
+        ("localization_and_globalization", 2),
+    ]);
+    assert!(crate::insert::insert(&index, &sound, payload(2)).is_ok());
+    let forged = forge("localization_and_globalization", "forged");
+    assert!(crate::insert::insert(&index, &forged, payload(3)).is_ok());
+    let expected = BTreeMap::from([
+        (b"internationalization".to_vec(), 2),
+        (b"localization_and_globalization".to_vec(), 2),
+    ]);
+    let terms = |index| {
+        vocabulary(index)
+            .into_iter()
+            .map(|(_, term, number_of_documents)| (term.unwrap(), number_of_documents))
+            .collect::<BTreeMap<_, _>>()
+    };
+    assert_eq!(terms(&index), expected);
+    let collision = maintain(&index).unwrap_err();
+    assert!(
+        collision
+            .to_string()
+            .contains("\"localization_and_globalization\" and \"forged\"")
+    );
+    assert_eq!(terms(&index), expected);
+}
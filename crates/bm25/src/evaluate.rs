@@ -12,14 +12,63 @@
 //
 // Copyright (c) 2025-2026 TensorChord Inc.
 
-use crate::bm25::{idf, length_to_fieldnorm, tf};
+use crate::bm25::{
+    Cache, Wand, fieldnorm_to_length, idf, length_to_fieldnorm, proximity_upper_bounds,
+};
 use crate::tuples::{JumpTuple, MetaTuple, TokenTuple, WithReader};
-use crate::vector::{Document, Query};
-use crate::{Opaque, address_tokens};
+use crate::vector::{Document, Element, Query};
+use crate::{Opaque, WIDTH, address_tokens};
 use index::relation::{Page, RelationRead};
 use score::Score;
+use std::collections::BTreeMap;
+
+/// How `evaluate` scores a document.
+pub struct Explanation {
+    /// Whether the document satisfies the clauses, `minimum_should_match`
+    /// and phrases of the query, without which it scores zero.
+    pub matched: bool,
+    pub number_of_documents: u32,
+    pub avgdl: f64,
+    pub fieldnorm: u8,
+    /// The document length as quantized by `fieldnorm`.
+    pub length: f64,
+    pub terms: Vec<TermExplanation>,
+    pub proximity: f64,
+}
+
+pub struct TermExplanation {
+    pub key: [u8; WIDTH],
+    pub weight: f64,
+    pub document_frequency: u32,
+    pub idf: f64,
+    pub term_frequency: f64,
+    pub score: f64,
+}
+
+impl Explanation {
+    pub fn score(&self) -> f64 {
+        if !self.matched {
+            return 0.0;
+        }
+        let mut result = 0.0;
+        for term in self.terms.iter() {
+            result += term.score;
+        }
+        result + self.proximity
+    }
+}
 
 pub fn evaluate<R: RelationRead>(index: &R, document: &Document, query: &Query) -> Score
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    if !matches(document, query) {
+        return Score::from_f64(0.0);
+    }
+    Score::from_f64(explain(index, document, query).score())
+}
+
+pub fn explain<R: RelationRead>(index: &R, document: &Document, query: &Query) -> Explanation
 where
     R::Page: Page<Opaque = Opaque>,
 {
@@ -28,8 +77,10 @@ where
     let meta_guard = index.read(0);
     let meta_bytes = meta_guard.get(1).expect("data corruption");
     let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
-    let k1 = meta_tuple.k1();
-    let b = meta_tuple.b();
+    let k1 = query.k1().unwrap_or(meta_tuple.k1());
+    let b = query.b().unwrap_or(meta_tuple.b());
+    let scoring = meta_tuple.scoring();
+    let unit = meta_tuple.unit();
     let ptr_jump = meta_tuple.ptr_jump();
     drop(meta_guard);
 
@@ -37,38 +88,308 @@ where
     let jump_bytes = jump_guard.get(1).expect("data corruption");
     let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
 
-    let sum_of_document_lengths = jump_tuple.sum_of_document_lengths();
-    let number_of_documents = jump_tuple.number_of_documents();
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+
+    let mut number_of_documents = 0_u32;
+    let mut sum_of_document_lengths = 0_u64;
+    for segment in segments.iter() {
+        number_of_documents += segment.number_of_documents;
+        sum_of_document_lengths += segment.sum_of_document_lengths;
+    }
     let avgdl = sum_of_document_lengths as f64 / number_of_documents as f64;
 
     let mut cursor = 0_usize;
 
-    let mut result = 0.0;
-    for &key in query.iter() {
-        let value = {
-            while cursor < document.len() && document.as_slice()[cursor].key < key {
-                cursor += 1;
+    let mut terms = Vec::new();
+    let mut proximity = Vec::new();
+    for (&key, &weight) in query.iter().zip(query.weights()) {
+        while cursor < document.len() && document.as_slice()[cursor].key < key {
+            cursor += 1;
+        }
+        let found = cursor < document.len() && document.as_slice()[cursor].key == key;
+        let term_frequency = if found {
+            document.as_slice()[cursor].value
+        } else {
+            0
+        };
+        let mut token_number_of_documents = 0_u32;
+        let mut token_sum_of_term_frequencies = 0_u64;
+        for segment in segments.iter() {
+            if let Some((token_guard, token_i)) =
+                address_tokens::read(index, segment.depth_tokens, segment.start_tokens, key)
+            {
+                let token_bytes = token_guard.get(token_i).expect("data corruption");
+                let token_tuple = TokenTuple::deserialize_ref(token_bytes);
+                token_number_of_documents += token_tuple.number_of_documents();
+                token_sum_of_term_frequencies += token_tuple.sum_of_term_frequencies();
             }
-            if cursor < document.len() && document.as_slice()[cursor].key == key {
-                document.as_slice()[cursor].value
-            } else {
-                continue;
+        }
+        let mut score = 0.0;
+        if found && token_number_of_documents != 0 {
+            let bm25 = Cache::new(
+                number_of_documents,
+                token_number_of_documents,
+                token_sum_of_term_frequencies,
+                k1,
+                b,
+                avgdl,
+                weight,
+                scoring,
+                unit,
+            );
+            score = bm25.evaluate(fieldnorm, term_frequency);
+            if let Some(positions) = document.positions() {
+                proximity.push((bm25.limit(), positions[cursor].as_slice()));
             }
-        };
-        let Some((token_guard, token_i)) = address_tokens::read(
-            index,
-            jump_tuple.depth_tokens(),
-            jump_tuple.start_tokens(),
+        }
+        terms.push(TermExplanation {
             key,
-        ) else {
+            weight,
+            document_frequency: token_number_of_documents,
+            idf: idf(number_of_documents, token_number_of_documents),
+            term_frequency: term_frequency as f64 * unit,
+            score,
+        });
+    }
+    let proximity = if query.proximity() != 0.0 {
+        crate::bm25::proximity(query.proximity(), &proximity)
+    } else {
+        0.0
+    };
+    Explanation {
+        matched: matches(document, query),
+        number_of_documents,
+        avgdl: avgdl * unit,
+        fieldnorm,
+        length: fieldnorm_to_length(fieldnorm) as f64 * unit,
+        terms,
+        proximity,
+    }
+}
+
+/// Returns the maximum score that a document of the index can get for the
+/// query, taken from the token upper bounds of segments and the documents not
+/// sealed yet. Documents not sealed yet are read only if the scoring function
+/// is unbounded, since `Cache::limit` bounds them otherwise.
+pub fn upper_bound<R: RelationRead>(index: &R, query: &Query) -> f64
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    let k1 = query.k1().unwrap_or(meta_tuple.k1());
+    let b = query.b().unwrap_or(meta_tuple.b());
+    let scoring = meta_tuple.scoring();
+    let unit = meta_tuple.unit();
+    let ptr_jump = meta_tuple.ptr_jump();
+    drop(meta_guard);
+
+    let jump_guard = index.read(ptr_jump);
+    let jump_bytes = jump_guard.get(1).expect("data corruption");
+    let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
+
+    // documents that are not sealed are in no token upper bound
+    let pending = {
+        let guard = index.read(jump_tuple.ptr_vectors());
+        guard.len() != 0 || guard.get_opaque().next != u32::MAX
+    };
+    let mut unsealed = query
+        .iter()
+        .map(|&key| (key, Wand::new()))
+        .collect::<BTreeMap<_, _>>();
+    if pending && !scoring.bounded() {
+        crate::vectors::read(index, jump_tuple.ptr_vectors(), |document| {
+            for Element { key, value } in document.elements {
+                if let Some(wand) = unsealed.get_mut(key) {
+                    wand.push(document.fieldnorm, *value);
+                }
+            }
+        });
+    }
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+
+    let mut number_of_documents = 0_u32;
+    let mut sum_of_document_lengths = 0_u64;
+    for segment in segments.iter() {
+        number_of_documents += segment.number_of_documents;
+        sum_of_document_lengths += segment.sum_of_document_lengths;
+    }
+    let avgdl = sum_of_document_lengths as f64 / number_of_documents as f64;
+
+    let mut result = 0.0;
+    let mut limits = Vec::new();
+    for (&key, &weight) in query.iter().zip(query.weights()) {
+        let mut token_number_of_documents = 0_u32;
+        let mut token_sum_of_term_frequencies = 0_u64;
+        let mut wands = Vec::new();
+        for segment in segments.iter() {
+            if let Some((token_guard, token_i)) =
+                address_tokens::read(index, segment.depth_tokens, segment.start_tokens, key)
+            {
+                let token_bytes = token_guard.get(token_i).expect("data corruption");
+                let token_tuple = TokenTuple::deserialize_ref(token_bytes);
+                token_number_of_documents += token_tuple.number_of_documents();
+                token_sum_of_term_frequencies += token_tuple.sum_of_term_frequencies();
+                wands.push((
+                    token_tuple.wand_fieldnorm(),
+                    token_tuple.wand_term_frequency(),
+                ));
+            }
+        }
+        if token_number_of_documents == 0 {
             continue;
+        }
+        let bm25 = Cache::new(
+            number_of_documents,
+            token_number_of_documents,
+            token_sum_of_term_frequencies,
+            k1,
+            b,
+            avgdl,
+            weight,
+            scoring,
+            unit,
+        );
+        let unsealed = &unsealed[&key];
+        let maximum = wands
+            .into_iter()
+            .chain([(unsealed.fieldnorm(), unsealed.term_frequency())])
+            .map(|(fieldnorm, term_frequency)| bm25.evaluate(fieldnorm, term_frequency))
+            .fold(0.0, f64::max);
+        result += if pending && scoring.bounded() {
+            maximum.max(bm25.limit())
+        } else {
+            maximum
+        };
+        limits.push(bm25.limit());
+    }
+    if query.proximity() != 0.0 {
+        result += proximity_upper_bounds(query.proximity(), &limits)
+            .into_iter()
+            .sum::<f64>();
+    }
+    result
+}
+
+/// Returns the idf of each term of the query multiplied by its weight, which
+/// is zero for a term that is in no segment.
+pub fn term_weights<R: RelationRead>(index: &R, query: &Query) -> Vec<f64>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    let ptr_jump = meta_tuple.ptr_jump();
+    drop(meta_guard);
+
+    let jump_guard = index.read(ptr_jump);
+    let jump_bytes = jump_guard.get(1).expect("data corruption");
+    let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
+
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+
+    let mut number_of_documents = 0_u32;
+    for segment in segments.iter() {
+        number_of_documents += segment.number_of_documents;
+    }
+
+    let mut result = Vec::with_capacity(query.len());
+    for (&key, &weight) in query.iter().zip(query.weights()) {
+        let mut token_number_of_documents = 0_u32;
+        for segment in segments.iter() {
+            if let Some((token_guard, token_i)) =
+                address_tokens::read(index, segment.depth_tokens, segment.start_tokens, key)
+            {
+                let token_bytes = token_guard.get(token_i).expect("data corruption");
+                let token_tuple = TokenTuple::deserialize_ref(token_bytes);
+                token_number_of_documents += token_tuple.number_of_documents();
+            }
+        }
+        if token_number_of_documents == 0 {
+            result.push(0.0);
+        } else {
+            result.push(weight * idf(number_of_documents, token_number_of_documents));
+        }
+    }
+    result
+}
+
+fn matches(document: &Document, query: &Query) -> bool {
+    if let Some(clauses) = query.clauses() {
+        let mut contains = |key: &_| {
+            document
+                .as_slice()
+                .binary_search_by_key(key, |element| element.key)
+                .is_ok()
+        };
+        if clauses.evaluate(&mut contains) != Some(true) {
+            return false;
+        }
+    }
+    if query.minimum_should_match() != 0 {
+        let count = document
+            .as_slice()
+            .iter()
+            .filter(|element| query.is_should(&element.key))
+            .count();
+        if count < query.minimum_should_match() {
+            return false;
+        }
+    }
+    if !query.phrases().is_empty() {
+        let Some(positions) = document.positions() else {
+            return false;
+        };
+        let mut positions = |key: &_| match document
+            .as_slice()
+            .binary_search_by_key(key, |element| element.key)
+        {
+            Ok(i) => positions[i].clone(),
+            Err(_) => Vec::new(),
+        };
+        if !query
+            .phrases()
+            .iter()
+            .all(|phrase| phrase.matches(query.slop(), &mut positions))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+#[test]
+fn upper_bound_bounds_unsealed() {
+    use crate::testing::*;
+    use crate::types::{Bm25IndexOptions, Bm25Scoring};
+    for scoring in [
+        Bm25Scoring::Bm25,
+        Bm25Scoring::Bm25Plus,
+        Bm25Scoring::Bm25L,
+        Bm25Scoring::TfIdf,
+        Bm25Scoring::LmDirichlet,
+        Bm25Scoring::LmJelinekMercer,
+    ] {
+        let options = Bm25IndexOptions {
+            scoring,
+            ..Default::default()
         };
-        let token_bytes = token_guard.get(token_i).expect("data corruption");
-        let token_tuple = TokenTuple::deserialize_ref(token_bytes);
-        let term_frequency = value;
-        let idf = idf(number_of_documents, token_tuple.number_of_documents());
-        let tf = tf(fieldnorm, term_frequency, k1, b, avgdl);
-        result += idf * tf;
+        let index = build(
+            options,
+            &[
+                document(&[("postgres", 1), ("index", 2)]),
+                document(&[("postgres", 2), ("vector", 1)]),
+            ],
+        );
+        let query = Query::new(vec![crate::vector::intern(&SEED, b"postgres")]);
+        let sealed = upper_bound(&index, &query);
+        // a term frequency beyond any of the segments
+        let pending = document(&[("postgres", 50)]);
+        crate::insert::insert(&index, &pending, payload(2)).unwrap();
+        let score = evaluate(&index, &pending, &query).to_f64();
+        assert!(sealed < score, "{scoring:?}");
+        assert!(score <= upper_bound(&index, &query), "{scoring:?}");
     }
-    Score::from_f64(result)
 }
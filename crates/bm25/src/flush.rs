@@ -20,29 +20,10 @@ use crate::{Opaque, address_documents, address_tokens, compression};
 use index::relation::{Page, RelationWrite};
 use index::tuples::Bool;
 
-pub struct Flushed {
-    pub number_of_documents: u32,
-    pub sum_of_document_lengths: u64,
-    pub width_1_documents: u16,
-    pub width_0_documents: u16,
-    pub depth_documents: u32,
-    pub start_documents: u32,
-    pub free_documents: u32,
-    pub depth_tokens: u32,
-    pub start_tokens: u32,
-    pub free_tokens: u32,
-    pub ptr_documents: u32,
-    pub ptr_tokens: u32,
-    pub ptr_summaries: u32,
-    pub ptr_blocks: u32,
-}
-
 pub fn flush<R: RelationWrite, D: IntoIterator<Item = Record>, M: IntoIterator<Item = Mapping>>(
-    k1: f64,
-    b: f64,
     index: &R,
     segment: Segment<D, M>,
-) -> Flushed
+) -> SegmentTuple
 where
     R::Page: Page<Opaque = Opaque>,
 {
@@ -63,15 +44,18 @@ where
         }));
     }
 
-    let avgdl = sum_of_document_lengths as f64 / number_of_documents as f64;
-
     let mut mappings = segment.mappings.into_iter().peekable();
     let mut map_tokens = Vec::new();
     let mut tape_tokens = TapeWriter::<_, TokenTuple>::create(index);
     let mut tape_summaries = TapeWriter::<_, SummaryTuple>::create(index);
     let mut tape_blocks = TapeWriter::<_, BlockTuple>::create(index);
+    let positions = segment.positions;
+    let mut tape_positions = positions
+        .as_ref()
+        .map(|_| TapeWriter::<_, PositionsTuple>::create(index));
     while let Some(token_id) = mappings.peek().map(|&Mapping(token_id, ..)| token_id) {
         let mut token_number_of_documents = 0_u32;
+        let mut token_sum_of_term_frequencies = 0_u64;
         let mut token_wand = Wand::new();
         let mut wptr_summaries = (tape_summaries.first(), 1);
         let mut ordinal = 0_usize;
@@ -79,34 +63,46 @@ where
             let block = {
                 let func = |Mapping(i, ..): &Mapping| &token_id == i;
                 let mut internal = Vec::with_capacity(128);
+                let mut offsets = Vec::with_capacity(128);
                 for _ in 0..128 {
-                    if let Some(Mapping(_, document_id, term_frequency)) = mappings.next_if(func) {
+                    if let Some(Mapping(_, document_id, term_frequency, offset)) =
+                        mappings.next_if(func)
+                    {
                         internal.push((document_id, term_frequency));
+                        offsets.push(offset);
                     } else {
                         break;
                     }
                 }
-                Block { internal }
+                Block { internal, offsets }
             };
             let (metadata_document_ids, compressed_document_ids) =
                 compression::compress_document_ids(block.min_document_id(), &block.document_ids());
             let (metadata_term_frequencies, compressed_term_frequencies) =
                 compression::compress_term_frequencies(&block.term_frequencies());
+            let wptr_positions = if let (Some(positions), Some(tape_positions)) =
+                (positions.as_ref(), tape_positions.as_mut())
+            {
+                let mut bytes = Vec::new();
+                for &offset in block.offsets.iter() {
+                    compression::compress_positions(&positions.get(offset), &mut bytes);
+                }
+                crate::positions::write(tape_positions, &bytes)
+            } else {
+                // page 0 is the meta page, so it never holds positions
+                (0, 0)
+            };
             let wptr_block = tape_blocks.push(BlockTuple {
                 metadata_document_ids,
                 compressed_document_ids,
                 metadata_term_frequencies,
                 compressed_term_frequencies,
+                wptr_positions: Pointer::new(wptr_positions),
             });
             let mut block_wand = Wand::new();
             for &(document_id, term_frequency) in block.internal() {
-                block_wand.push(
-                    fieldnorms[document_id as usize],
-                    term_frequency,
-                    k1,
-                    b,
-                    avgdl,
-                );
+                block_wand.push(fieldnorms[document_id as usize], term_frequency);
+                token_sum_of_term_frequencies += term_frequency as u64;
             }
             token_number_of_documents += block.number_of_documents() as u32;
             token_wand.extend(&block_wand);
@@ -128,6 +124,7 @@ where
             tape_tokens.push(TokenTuple {
                 id: token_id,
                 number_of_documents: token_number_of_documents,
+                sum_of_term_frequencies: token_sum_of_term_frequencies,
                 wand_fieldnorm: token_wand.fieldnorm(),
                 wand_term_frequency: token_wand.term_frequency(),
                 wptr_summaries,
@@ -139,8 +136,9 @@ where
         address_documents::write(index, &map_documents);
     let (depth_tokens, start_tokens, free_tokens) = address_tokens::write(index, &map_tokens);
 
-    Flushed {
+    SegmentTuple {
         number_of_documents,
+        number_of_deleted_documents: 0,
         sum_of_document_lengths,
         width_1_documents,
         width_0_documents,
@@ -154,11 +152,13 @@ where
         ptr_tokens: { tape_tokens }.first(),
         ptr_summaries: { tape_summaries }.first(),
         ptr_blocks: { tape_blocks }.first(),
+        ptr_positions: tape_positions.map_or(u32::MAX, |tape| tape.first()),
     }
 }
 
 struct Block {
     internal: Vec<(u32, u32)>,
+    offsets: Vec<u64>,
 }
 
 impl Block {
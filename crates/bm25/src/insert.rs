@@ -14,13 +14,21 @@
 
 use crate::Opaque;
 use crate::bm25::length_to_fieldnorm;
+use crate::dictionary::Collision;
 use crate::tape::TapeWriter;
 use crate::tuples::{JumpTuple, MetaTuple, VectorTuple, WithReader};
 use crate::vector::Document;
 use index::relation::{Page, RelationRead, RelationWrite};
 use index::tuples::Bool;
 
-pub fn insert<R: RelationRead + RelationWrite>(index: &R, document: &Document, payload: [u16; 3])
+/// Appends a document to documents that are not sealed yet, returning the
+/// number of their pages. A document with a string of a hashed key that
+/// collides with another string of the dictionary is rejected.
+pub fn insert<R: RelationRead + RelationWrite>(
+    index: &R,
+    document: &Document,
+    payload: [u16; 3],
+) -> Result<u32, Collision>
 where
     R::Page: Page<Opaque = Opaque>,
 {
@@ -31,14 +39,23 @@ where
     let meta_bytes = meta_guard.get(1).expect("data corruption");
     let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
     let ptr_jump = meta_tuple.ptr_jump();
+    let positions = meta_tuple.positions();
+    let dictionary = meta_tuple.dictionary();
     drop(meta_guard);
 
     let jump_guard = index.read(ptr_jump);
     let jump_bytes = jump_guard.get(1).expect("data corruption");
     let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
 
+    if dictionary {
+        let depth_terms = jump_tuple.depth_terms();
+        let start_terms = jump_tuple.start_terms();
+        crate::dictionary::check(index, depth_terms, start_terms, document)?;
+    }
+
     let first = jump_tuple.ptr_vectors();
     let mut current = first;
+    let mut pages = 1_u32;
     let head = loop {
         let read = index.read(current);
         if read.get_opaque().next == u32::MAX {
@@ -51,10 +68,45 @@ where
         } else {
             current = read.get_opaque().next;
         }
+        pages += 1;
     };
 
     let mut tape = TapeWriter::from_guard(index, head);
     tape.push(VectorTuple::_2 { fieldnorm });
+    if positions {
+        let mut bytes = Vec::new();
+        for positions in document.positions().expect("positions are missing") {
+            crate::compression::compress_positions(positions, &mut bytes);
+        }
+        let mut remain = bytes.as_slice();
+        while !remain.is_empty() {
+            if let Some(w) = VectorTuple::fit_3(tape.freespace()) {
+                let (left, right) = remain.split_at(std::cmp::min(w, remain.len()));
+                tape.tape_put(VectorTuple::_3 {
+                    positions: left.to_vec(),
+                });
+                remain = right;
+            } else {
+                tape.tape_move();
+            }
+        }
+    }
+    if dictionary {
+        let terms = document.terms().expect("terms are missing");
+        let bytes = crate::dictionary::encode(document.as_slice(), terms);
+        let mut remain = bytes.as_slice();
+        while !remain.is_empty() {
+            if let Some(w) = VectorTuple::fit_4(tape.freespace()) {
+                let (left, right) = remain.split_at(std::cmp::min(w, remain.len()));
+                tape.tape_put(VectorTuple::_4 {
+                    terms: left.to_vec(),
+                });
+                remain = right;
+            } else {
+                tape.tape_move();
+            }
+        }
+    }
     let mut remain = document.as_slice();
     loop {
         let freespace = tape.freespace();
@@ -76,4 +128,5 @@ where
             tape.tape_move();
         }
     }
+    Ok(pages)
 }
@@ -12,8 +12,10 @@
 //
 // Copyright (c) 2025-2026 TensorChord Inc.
 
+use crate::WIDTH;
+use crate::dictionary::Dictionary;
 use crate::segment::{Mapping, Record, Segment};
-use crate::vector::{Document, Element};
+use crate::vector::{Document, Element, is_hashed};
 use always_equal::AlwaysEqual;
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
@@ -66,6 +68,69 @@ impl RecordsWriter {
     }
 }
 
+pub struct PositionsWriter {
+    file: BufWriter<File>,
+    len: u64,
+}
+
+impl PositionsWriter {
+    pub fn create(file: File) -> Self {
+        Self {
+            file: BufWriter::with_capacity(64 * 1024, file),
+            len: 0,
+        }
+    }
+    #[must_use]
+    pub fn write(&mut self, positions: &[u32]) -> u64 {
+        let offset = self.len;
+        let mut buffer = Vec::new();
+        crate::compression::compress_positions(positions, &mut buffer);
+        self.len += buffer.len() as u64;
+        handle_io_error(self.file.write_all(&buffer));
+        offset
+    }
+    pub fn flush(&mut self) {
+        handle_io_error(self.file.flush());
+    }
+}
+
+/// Strings of hashed keys, which are deduplicated in memory and written on
+/// flush.
+pub struct TermsWriter {
+    file: File,
+    dictionary: Dictionary,
+}
+
+impl TermsWriter {
+    pub fn create(file: File) -> Self {
+        Self {
+            file,
+            dictionary: Dictionary::new(),
+        }
+    }
+    pub fn write(&mut self, document: &Document) {
+        let terms = document.terms().expect("terms are missing");
+        for (Element { key, .. }, term) in document.iter().zip(terms) {
+            if is_hashed(key) {
+                if let Err(collision) = self.dictionary.insert(*key, term) {
+                    panic!("{collision}");
+                }
+            }
+        }
+    }
+    pub fn flush(&mut self) {
+        let mut buffer = Vec::new();
+        for (key, term) in self.dictionary.iter() {
+            buffer.extend_from_slice(&key);
+            buffer.extend((term.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(term);
+        }
+        handle_io_error(self.file.write_all(&buffer));
+        handle_io_error(self.file.flush());
+        self.dictionary = Dictionary::new();
+    }
+}
+
 pub struct MappingsWriter {
     file: Box<dyn FnMut() -> File>,
     capacity: usize,
@@ -128,20 +193,20 @@ impl Iterator for RecordsReader {
     }
 }
 
+type Offsets = (u32, u64);
+
 pub struct MappingsReader {
-    collection: BinaryHeap<(Reverse<Mapping>, AlwaysEqual<(u32, BufReader<File>)>)>,
+    collection: BinaryHeap<(Reverse<Mapping>, AlwaysEqual<(Offsets, BufReader<File>)>)>,
 }
 
 impl MappingsReader {
-    pub fn open(iter: impl Iterator<Item = (u32, File)>) -> Self {
+    pub fn open(iter: impl Iterator<Item = (Offsets, File)>) -> Self {
         let mut collection = Vec::new();
-        for (offset, file) in iter {
+        for (offsets, file) in iter {
             let mut reader = BufReader::with_capacity(64 * 1024, file);
             if !handle_io_error(reader.fill_buf()).is_empty() {
-                let mut element = Mapping::new_zeroed();
-                handle_io_error(reader.read_exact(element.as_mut_bytes()));
-                element.1 += offset;
-                collection.push((Reverse(element), AlwaysEqual((offset, reader))));
+                let element = read_mapping(&mut reader, offsets);
+                collection.push((Reverse(element), AlwaysEqual((offsets, reader))));
             }
         }
         Self {
@@ -154,18 +219,43 @@ impl Iterator for MappingsReader {
     type Item = Mapping;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (Reverse(result), AlwaysEqual((offset, mut reader))) = self.collection.pop()?;
+        let (Reverse(result), AlwaysEqual((offsets, mut reader))) = self.collection.pop()?;
         if !handle_io_error(reader.fill_buf()).is_empty() {
             let collection = &mut self.collection;
-            let mut element = Mapping::new_zeroed();
-            handle_io_error(reader.read_exact(element.as_mut_bytes()));
-            element.1 += offset;
-            collection.push((Reverse(element), AlwaysEqual((offset, reader))));
+            let element = read_mapping(&mut reader, offsets);
+            collection.push((Reverse(element), AlwaysEqual((offsets, reader))));
         }
         Some(result)
     }
 }
 
+fn read_mapping(
+    reader: &mut BufReader<File>,
+    (document_offset, positions_offset): Offsets,
+) -> Mapping {
+    let mut element = Mapping::new_zeroed();
+    handle_io_error(reader.read_exact(element.as_mut_bytes()));
+    element.1 += document_offset;
+    if element.3 != u64::MAX {
+        element.3 += positions_offset;
+    }
+    element
+}
+
+/// Positions files of all participants, addressed as if they were concatenated.
+pub struct PositionsReader {
+    memmaps: Vec<(u64, memmap2::Mmap)>,
+}
+
+impl PositionsReader {
+    pub fn get(&self, offset: u64) -> Vec<u32> {
+        let i = self.memmaps.partition_point(|&(start, _)| start <= offset) - 1;
+        let (start, memmap) = &self.memmaps[i];
+        let mut bytes = &memmap[(offset - start) as usize..];
+        crate::compression::decompress_positions(&mut bytes)
+    }
+}
+
 pub fn records_writer(dir: impl AsRef<Path>, code: u32) -> RecordsWriter {
     let dir = dir.as_ref();
     let filename = format!("records.{code:08x}");
@@ -173,6 +263,20 @@ pub fn records_writer(dir: impl AsRef<Path>, code: u32) -> RecordsWriter {
     RecordsWriter::create(file)
 }
 
+pub fn positions_writer(dir: impl AsRef<Path>, code: u32) -> PositionsWriter {
+    let dir = dir.as_ref();
+    let filename = format!("positions.{code:08x}");
+    let file = handle_io_error(File::create_new(dir.join(filename)));
+    PositionsWriter::create(file)
+}
+
+pub fn terms_writer(dir: impl AsRef<Path>, code: u32) -> TermsWriter {
+    let dir = dir.as_ref();
+    let filename = format!("terms.{code:08x}");
+    let file = handle_io_error(File::create_new(dir.join(filename)));
+    TermsWriter::create(file)
+}
+
 pub fn mappings_writer(dir: impl AsRef<Path>, code: u32) -> MappingsWriter {
     let dir = dir.as_ref().to_path_buf();
     let mut number = 0_u32;
@@ -187,12 +291,25 @@ pub fn mappings_writer(dir: impl AsRef<Path>, code: u32) -> MappingsWriter {
 pub fn write(
     records_writer: &mut RecordsWriter,
     mappings_writer: &mut MappingsWriter,
+    positions_writer: Option<&mut PositionsWriter>,
+    terms_writer: Option<&mut TermsWriter>,
     document: &Document,
     payload: [u16; 3],
 ) {
     let document_id = records_writer.write(Record(document.length(), payload));
-    for &Element { key, value } in document.iter() {
-        mappings_writer.write(Mapping(key, document_id, value));
+    if let Some(terms_writer) = terms_writer {
+        terms_writer.write(document);
+    }
+    if let Some(positions_writer) = positions_writer {
+        let positions = document.positions().expect("positions are missing");
+        for (&Element { key, value }, positions) in document.iter().zip(positions) {
+            let offset = positions_writer.write(positions);
+            mappings_writer.write(Mapping(key, document_id, value, offset));
+        }
+    } else {
+        for &Element { key, value } in document.iter() {
+            mappings_writer.write(Mapping(key, document_id, value, u64::MAX));
+        }
     }
 }
 
@@ -210,10 +327,10 @@ pub fn locally_merge(dir: impl AsRef<Path>, code: u32) {
     while start + 1 < end {
         let pivot = start.saturating_add(32).min(end);
         let iter = (start..pivot).flat_map(move |number| {
-            let offset = 0_u32;
+            let offsets = (0_u32, 0_u64);
             let filename = format!("mappings.{code:08x}.{number:08x}");
             let file = handle_io_error(File::open(dir.join(filename)));
-            Some((offset, file))
+            Some((offsets, file))
         });
         let reader = MappingsReader::open(iter);
         let file = {
@@ -241,7 +358,11 @@ pub fn locally_merge(dir: impl AsRef<Path>, code: u32) {
     }
 }
 
-pub fn readers(dir: impl AsRef<Path>, total: u32) -> Segment<RecordsReader, MappingsReader> {
+pub fn readers(
+    dir: impl AsRef<Path>,
+    total: u32,
+    positions: bool,
+) -> Segment<RecordsReader, MappingsReader> {
     let dir = dir.as_ref().to_path_buf();
     let offsets = {
         let mut offsets = Vec::with_capacity(total as usize);
@@ -258,6 +379,27 @@ pub fn readers(dir: impl AsRef<Path>, total: u32) -> Segment<RecordsReader, Mapp
         }
         offsets
     };
+    let (positions_offsets, positions_reader) = if positions {
+        let mut offsets = Vec::with_capacity(total as usize);
+        let mut memmaps = Vec::new();
+        let mut offset = 0_u64;
+        for code in 0..total {
+            offsets.push(offset);
+            let filename = format!("positions.{code:08x}");
+            if let Some(file) = not_found_is_okay(File::open(dir.join(filename))) {
+                let len = handle_io_error(file.metadata()).len();
+                if len != 0 {
+                    #[allow(unsafe_code)]
+                    let memmap = unsafe { handle_io_error(memmap2::Mmap::map(&file)) };
+                    memmaps.push((offset, memmap));
+                    offset += len;
+                }
+            }
+        }
+        (offsets, Some(PositionsReader { memmaps }))
+    } else {
+        (vec![0_u64; total as usize], None)
+    };
     let records_reader = {
         let dir = dir.clone();
         let iter = (0..total).flat_map(move |code| {
@@ -268,15 +410,49 @@ pub fn readers(dir: impl AsRef<Path>, total: u32) -> Segment<RecordsReader, Mapp
     };
     let mappings_reader = {
         let iter = (0..total).flat_map(|code| {
-            let offset = offsets[code as usize];
+            let offsets = (offsets[code as usize], positions_offsets[code as usize]);
             let filename = format!("mappings.{code:08x}");
             let file = not_found_is_okay(File::open(dir.join(filename)))?;
-            Some((offset, file))
+            Some((offsets, file))
         });
         MappingsReader::open(iter)
     };
     Segment {
         records: records_reader,
         mappings: mappings_reader,
+        positions: positions_reader,
+    }
+}
+
+/// Reads strings of hashed keys written by all participants.
+pub fn terms(dir: impl AsRef<Path>, total: u32) -> Dictionary {
+    let dir = dir.as_ref();
+    let mut dictionary = Dictionary::new();
+    for code in 0..total {
+        let filename = format!("terms.{code:08x}");
+        let Some(mut file) = not_found_is_okay(File::open(dir.join(filename))) else {
+            continue;
+        };
+        let mut buffer = Vec::new();
+        handle_io_error(file.read_to_end(&mut buffer));
+        let mut bytes = buffer.as_slice();
+        while !bytes.is_empty() {
+            let Some((key, rest)) = bytes.split_first_chunk::<WIDTH>() else {
+                panic!("IO error occurred during external sorting: data corruption")
+            };
+            let Some((len, rest)) = rest.split_first_chunk::<4>() else {
+                panic!("IO error occurred during external sorting: data corruption")
+            };
+            let len = u32::from_le_bytes(*len) as usize;
+            if rest.len() < len {
+                panic!("IO error occurred during external sorting: data corruption")
+            }
+            let (term, rest) = rest.split_at(len);
+            if let Err(collision) = dictionary.insert(*key, term) {
+                panic!("{collision}");
+            }
+            bytes = rest;
+        }
     }
+    dictionary
 }
@@ -18,13 +18,21 @@ mod bm25;
 mod build;
 mod bulkdelete;
 mod compression;
+mod dictionary;
 mod evaluate;
 mod flush;
 mod insert;
 mod maintain;
+mod positions;
 mod search;
+mod segments;
+mod stats;
 mod tape;
+#[cfg(test)]
+mod testing;
 mod tuples;
+mod vectors;
+mod verify;
 
 pub mod io;
 pub mod seed;
@@ -59,7 +67,10 @@ unsafe impl index::relation::Opaque for Opaque {
 
 pub use build::build;
 pub use bulkdelete::bulkdelete;
-pub use evaluate::evaluate;
+pub use dictionary::{Collision, Dictionary, expand, vocabulary};
+pub use evaluate::{Explanation, TermExplanation, evaluate, explain, term_weights, upper_bound};
 pub use insert::insert;
-pub use maintain::maintain;
-pub use search::search;
+pub use maintain::{maintain, optimize, seal};
+pub use search::{matching, search, search_with_terms};
+pub use stats::{IndexStats, Pages, TermStats, index_stats, term_stats};
+pub use verify::{Problem, verify};
@@ -12,11 +12,12 @@
 //
 // Copyright (c) 2025-2026 TensorChord Inc.
 
-use crate::io::{MappingsWriter, RecordsWriter, handle_io_error};
+use crate::dictionary::{Collision, Dictionary};
+use crate::io::{MappingsWriter, PositionsWriter, RecordsWriter, handle_io_error};
 use crate::segment::{Mapping, Record};
-use crate::tape::TapeReader;
+use crate::tape::{TapeReader, TapeWriter};
 use crate::tuples::*;
-use crate::vector::Document;
+use crate::vector::{Document, Element};
 use crate::{Opaque, WIDTH, compression};
 use index::relation::{Page, PageGuard, RelationRead, RelationWrite};
 use std::fs::{File, OpenOptions};
@@ -24,49 +25,222 @@ use std::io::BufWriter;
 use std::path::Path;
 use zerocopy::{FromBytes, IntoBytes};
 
+const MERGE_FACTOR: usize = 8;
+const MERGE_FLOOR: u64 = 1024;
+
 pub fn maintain<R: RelationRead + RelationWrite>(
     index: &R,
-    _check: impl Fn(),
+    check: impl Fn(),
     dir: &Path,
     file: &Path,
-) where
+) -> Result<(), Collision>
+where
     R::Page: Page<Opaque = Opaque>,
 {
     let meta_guard = index.read(0);
     let meta_bytes = meta_guard.get(1).expect("data corruption");
     let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
-    let k1 = meta_tuple.k1();
-    let b = meta_tuple.b();
     let ptr_lock = meta_tuple.ptr_lock();
     let ptr_jump = meta_tuple.ptr_jump();
     drop(meta_guard);
 
     let _lock_guard = index.write(ptr_lock);
 
-    let mut relabel = BufWriter::with_capacity(
-        16 * 1024,
-        handle_io_error(OpenOptions::new().read(true).write(true).open(file)),
-    );
+    compact(index, check, dir, file, ptr_jump, u32::MAX)
+}
+
+pub fn seal<R: RelationRead + RelationWrite>(
+    index: &R,
+    threshold: u32,
+    check: impl Fn(),
+    dir: &Path,
+    file: &Path,
+) -> Result<bool, Collision>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    let ptr_lock = meta_tuple.ptr_lock();
+    let ptr_jump = meta_tuple.ptr_jump();
+    drop(meta_guard);
+
+    let Some(_lock_guard) = index.try_write(ptr_lock) else {
+        return Ok(false);
+    };
+
+    // the last page is counted even if it is empty, so a threshold of 1 seals
+    // on every insertion
+    let pages = {
+        let jump_guard = index.read(ptr_jump);
+        let jump_bytes = jump_guard.get(1).expect("data corruption");
+        let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
+        let mut pages = 0_u32;
+        let mut current = jump_tuple.ptr_vectors();
+        while current != u32::MAX {
+            pages += 1;
+            current = index.read(current).get_opaque().next;
+        }
+        pages
+    };
+    if pages < threshold {
+        return Ok(false);
+    }
+
+    // only the bottom tier is merged, so that insertion never rewrites large
+    // segments, while the number of segments stays bounded
+    compact(index, check, dir, file, ptr_jump, 1)?;
+    Ok(true)
+}
+
+pub fn optimize<R: RelationRead + RelationWrite>(
+    index: &R,
+    check: impl Fn(),
+    dir: &Path,
+    file: &Path,
+) -> Result<bool, Collision>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    let ptr_lock = meta_tuple.ptr_lock();
+    let ptr_jump = meta_tuple.ptr_jump();
+    drop(meta_guard);
+
+    let Some(_lock_guard) = index.try_write(ptr_lock) else {
+        return Ok(false);
+    };
+
+    let pending = {
+        let jump_guard = index.read(ptr_jump);
+        let jump_bytes = jump_guard.get(1).expect("data corruption");
+        let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
+        let unsealed = {
+            let guard = index.read(jump_tuple.ptr_vectors());
+            guard.len() != 0 || guard.get_opaque().next != u32::MAX
+        };
+        let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+        unsealed
+            || select(
+                &segments
+                    .iter()
+                    .map(|segment| {
+                        (
+                            segment.number_of_documents,
+                            segment.number_of_deleted_documents,
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+                0,
+                u32::MAX,
+            )
+            .contains(&true)
+    };
+    if !pending {
+        return Ok(false);
+    }
+
+    compact(index, check, dir, file, ptr_jump, u32::MAX)?;
+    Ok(true)
+}
+
+fn compact<R: RelationRead + RelationWrite>(
+    index: &R,
+    check: impl Fn(),
+    dir: &Path,
+    file: &Path,
+    ptr_jump: u32,
+    tiers: u32,
+) -> Result<(), Collision>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let positions = crate::seed::positions(index);
+    let dictionary = crate::seed::dictionary(index);
     let mut records_writer = crate::io::records_writer(dir, 0);
+    let mut mappings_writer = crate::io::mappings_writer(dir, 0);
+    let mut positions_writer = positions.then(|| crate::io::positions_writer(dir, 0));
+    let mut number_of_documents = 0_u32;
 
     let jump_guard = index.read(ptr_jump);
     let jump_bytes = jump_guard.get(1).expect("data corruption");
     let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
 
-    {
-        let first = jump_tuple.ptr_documents();
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+
+    let mut terms = dictionary.then(|| Dictionary::read(index, jump_tuple.ptr_terms()));
+    let number_of_terms = terms.as_ref().map_or(0, Dictionary::len);
+
+    // documents colliding with each other or with the dictionary are found
+    // here, before the index is changed
+    let mut collision = None;
+    let ptr_vectors = {
+        let mut head = crate::vectors::seal(index, jump_tuple.ptr_vectors(), &check, |unsealed| {
+            if let Some(terms) = terms.as_mut()
+                && let Err(e) =
+                    crate::dictionary::decode(unsealed.elements, unsealed.strings, terms)
+            {
+                collision.get_or_insert(e);
+            }
+            let document = document(unsealed.elements.to_vec(), unsealed.positions, positions);
+            crate::io::write(
+                &mut records_writer,
+                &mut mappings_writer,
+                positions_writer.as_mut(),
+                None,
+                &document,
+                unsealed.payload,
+            );
+            number_of_documents += 1;
+        });
+        if let Some(collision) = collision {
+            return Err(collision);
+        }
+        let fresh = index.alloc(Opaque {
+            next: u32::MAX,
+            flags: 0,
+        });
+        head.get_opaque_mut().next = fresh.id();
+        fresh.id()
+    };
+
+    let merging = select(
+        &segments
+            .iter()
+            .map(|segment| {
+                (
+                    segment.number_of_documents,
+                    segment.number_of_deleted_documents,
+                )
+            })
+            .collect::<Vec<_>>(),
+        number_of_documents,
+        tiers,
+    );
+
+    let mut relabel = BufWriter::with_capacity(
+        16 * 1024,
+        handle_io_error(OpenOptions::new().read(true).write(true).open(file)),
+    );
+
+    for (segment, _) in segments.iter().zip(merging.iter()).filter(|(_, x)| **x) {
+        let first = segment.ptr_documents;
         assert!(first != u32::MAX);
         let mut current = first;
         while current != u32::MAX {
+            check();
             let guard = index.read(current);
             for i in 1..=guard.len() {
                 let bytes = guard.get(i).expect("data corruption");
                 let tuple = DocumentTuple::deserialize_ref(bytes);
-                add_document(
-                    &mut relabel,
-                    &mut records_writer,
-                    (!bool::from(tuple.deleted())).then_some(tuple.payload()),
-                );
+                let payload = (!bool::from(tuple.deleted())).then_some(tuple.payload());
+                if payload.is_some() {
+                    number_of_documents += 1;
+                }
+                add_document(&mut relabel, &mut records_writer, payload);
             }
             current = guard.get_opaque().next;
         }
@@ -99,33 +273,37 @@ pub fn maintain<R: RelationRead + RelationWrite>(
     } else {
         &mut []
     };
-    let mut mappings_writer = crate::io::mappings_writer(dir, 0);
 
-    {
-        let mut tape_tokens = TapeReader::new(jump_tuple.ptr_tokens(), |bytes| {
+    let mut offset = 0_usize;
+    for (segment, _) in segments.iter().zip(merging.iter()).filter(|(_, x)| **x) {
+        let relabel_slice = &relabel_slice[offset..][..segment.number_of_documents as usize];
+        offset += segment.number_of_documents as usize;
+        let mut tape_tokens = TapeReader::new(segment.ptr_tokens, |bytes| {
             let token_tuple = TokenTuple::deserialize_ref(bytes);
             Token {
                 id: token_tuple.id(),
                 number_of_documents: token_tuple.number_of_documents(),
             }
         });
-        let mut tape_summaries = TapeReader::new(jump_tuple.ptr_summaries(), |bytes| {
+        let mut tape_summaries = TapeReader::new(segment.ptr_summaries, |bytes| {
             let summary_tuple = SummaryTuple::deserialize_ref(bytes);
             Summary {
                 min_document_id: summary_tuple.min_document_id(),
                 number_of_documents: summary_tuple.number_of_documents(),
             }
         });
-        let mut tape_blocks = TapeReader::new(jump_tuple.ptr_blocks(), |bytes| {
+        let mut tape_blocks = TapeReader::new(segment.ptr_blocks, |bytes| {
             let block_tuple = BlockTuple::deserialize_ref(bytes);
             Block {
                 metadata_document_ids: block_tuple.metadata_document_ids(),
                 compressed_document_ids: block_tuple.compressed_document_ids().to_vec(),
                 metadata_term_frequencies: block_tuple.metadata_term_frequencies(),
                 compressed_term_frequencies: block_tuple.compressed_term_frequencies().to_vec(),
+                wptr_positions: block_tuple.wptr_positions().into_inner(),
             }
         });
         while let Some(token) = tape_tokens.next(index) {
+            check();
             for _ in 0..token.number_of_documents.div_ceil(128) {
                 let summary = tape_summaries.next(index).expect("data corruption");
                 let block = tape_blocks.next(index).expect("data corruption");
@@ -142,13 +320,22 @@ pub fn maintain<R: RelationRead + RelationWrite>(
                     &block.compressed_term_frequencies,
                     &mut term_frequencies,
                 );
+                let bytes = if positions {
+                    crate::positions::read(index, block.wptr_positions)
+                } else {
+                    Vec::new()
+                };
+                let mut bytes = bytes.as_slice();
                 for i in 0..summary.number_of_documents {
                     let document_id = document_ids.as_slice()[i as usize];
                     let term_frequency = term_frequencies.as_slice()[i as usize];
+                    let positions =
+                        positions.then(|| compression::decompress_positions(&mut bytes));
                     add_element(
                         relabel_slice,
                         records_slice,
                         &mut mappings_writer,
+                        positions_writer.as_mut().zip(positions.as_deref()),
                         token.id,
                         document_id,
                         term_frequency,
@@ -164,136 +351,82 @@ pub fn maintain<R: RelationRead + RelationWrite>(
     drop(relabel_memmap);
     drop(relabel);
 
-    let ptr_vectors = {
-        let first = jump_tuple.ptr_vectors();
-        assert!(first != u32::MAX);
-        let mut state = None;
-        let mut current = first;
-        let mut head = loop {
-            let read = index.read(current);
-            if read.get_opaque().next == u32::MAX {
-                drop(read);
-                let write = index.write(current);
-                for i in 1..=write.len() {
-                    let vector_bytes = write.get(i).expect("data corruption");
-                    let vector_tuple = VectorTuple::deserialize_ref(vector_bytes);
-                    match vector_tuple {
-                        VectorTupleReader::_2(_) => {
-                            state = Some(Vec::new());
-                        }
-                        VectorTupleReader::_1(vector_tuple) => {
-                            if let Some(internal) = state.as_mut() {
-                                internal.extend(vector_tuple.elements());
-                            } else {
-                                panic!("data corruption");
-                            }
-                        }
-                        VectorTupleReader::_0(vector_tuple) => {
-                            if let Some(mut internal) = state.take() {
-                                if !bool::from(vector_tuple.deleted()) {
-                                    internal.extend(vector_tuple.elements());
-                                    let document = Document::new(internal);
-                                    crate::io::write(
-                                        &mut records_writer,
-                                        &mut mappings_writer,
-                                        &document,
-                                        vector_tuple.payload(),
-                                    );
-                                }
-                            } else {
-                                panic!("data corruption");
-                            }
-                        }
-                    }
-                }
-                if write.get_opaque().next == u32::MAX {
-                    break write;
-                }
-                current = write.get_opaque().next;
-            } else {
-                for i in 1..=read.len() {
-                    let vector_bytes = read.get(i).expect("data corruption");
-                    let vector_tuple = VectorTuple::deserialize_ref(vector_bytes);
-                    match vector_tuple {
-                        VectorTupleReader::_2(_) => {
-                            state = Some(Vec::new());
-                        }
-                        VectorTupleReader::_1(vector_tuple) => {
-                            if let Some(internal) = state.as_mut() {
-                                internal.extend(vector_tuple.elements());
-                            } else {
-                                panic!("data corruption");
-                            }
-                        }
-                        VectorTupleReader::_0(vector_tuple) => {
-                            if let Some(mut internal) = state.take() {
-                                if !bool::from(vector_tuple.deleted()) {
-                                    internal.extend(vector_tuple.elements());
-                                    let document = Document::new(internal);
-                                    crate::io::write(
-                                        &mut records_writer,
-                                        &mut mappings_writer,
-                                        &document,
-                                        vector_tuple.payload(),
-                                    );
-                                }
-                            } else {
-                                panic!("data corruption");
-                            }
-                        }
-                    }
-                }
-                current = read.get_opaque().next;
-            }
-        };
-        let fresh = index.alloc(Opaque {
-            next: u32::MAX,
-            flags: 0,
-        });
-        head.get_opaque_mut().next = fresh.id();
-        fresh.id()
-    };
-
     drop(jump_guard);
 
     records_writer.flush();
     mappings_writer.flush();
+    if let Some(positions_writer) = positions_writer.as_mut() {
+        positions_writer.flush();
+    }
     drop(records_writer);
     drop(mappings_writer);
-    crate::io::locally_merge(dir, 0);
+    drop(positions_writer);
+
+    let flushed = if number_of_documents != 0 {
+        crate::io::locally_merge(dir, 0);
+        let segment = crate::io::readers(dir, 1, positions);
+        Some(crate::flush::flush(index, segment))
+    } else {
+        None
+    };
 
-    let segment = crate::io::readers(dir, 1);
-    let flushed = crate::flush::flush(k1, b, index, segment);
+    let written = terms
+        .filter(|terms| terms.len() != number_of_terms)
+        .map(|terms| terms.write(index));
+
+    let mut tape_segments = TapeWriter::<_, SegmentTuple>::create(index);
+    for (segment, _) in segments.iter().zip(merging.iter()).filter(|(_, x)| !**x) {
+        tape_segments.push(segment.clone());
+    }
+    if let Some(flushed) = flushed {
+        tape_segments.push(flushed);
+    }
+    let ptr_segments = { tape_segments }.first();
 
     let mut jump_guard = index.write(ptr_jump);
     let jump_bytes = jump_guard.get_mut(1).expect("data corruption");
     let mut jump_tuple = JumpTuple::deserialize_mut(jump_bytes);
 
-    let recycle = [
+    let mut recycle = vec![
         (*jump_tuple.ptr_vectors(), ptr_vectors),
-        (*jump_tuple.free_documents(), u32::MAX),
-        (*jump_tuple.free_tokens(), u32::MAX),
-        (*jump_tuple.ptr_documents(), u32::MAX),
-        (*jump_tuple.ptr_tokens(), u32::MAX),
-        (*jump_tuple.ptr_summaries(), u32::MAX),
-        (*jump_tuple.ptr_blocks(), u32::MAX),
+        (*jump_tuple.ptr_segments(), u32::MAX),
     ];
+    if let Some(
+        [
+            (ptr_terms, depth_terms, start_terms, free_terms),
+            (ptr_strings, depth_strings, start_strings, free_strings),
+        ],
+    ) = written
+    {
+        recycle.extend([
+            (*jump_tuple.ptr_terms(), u32::MAX),
+            (*jump_tuple.free_terms(), u32::MAX),
+            (*jump_tuple.ptr_strings(), u32::MAX),
+            (*jump_tuple.free_strings(), u32::MAX),
+        ]);
+        *jump_tuple.ptr_terms() = ptr_terms;
+        *jump_tuple.depth_terms() = depth_terms;
+        *jump_tuple.start_terms() = start_terms;
+        *jump_tuple.free_terms() = free_terms;
+        *jump_tuple.ptr_strings() = ptr_strings;
+        *jump_tuple.depth_strings() = depth_strings;
+        *jump_tuple.start_strings() = start_strings;
+        *jump_tuple.free_strings() = free_strings;
+    }
+    for (segment, _) in segments.iter().zip(merging.iter()).filter(|(_, x)| **x) {
+        recycle.extend([
+            (segment.free_documents, u32::MAX),
+            (segment.free_tokens, u32::MAX),
+            (segment.ptr_documents, u32::MAX),
+            (segment.ptr_tokens, u32::MAX),
+            (segment.ptr_summaries, u32::MAX),
+            (segment.ptr_blocks, u32::MAX),
+            (segment.ptr_positions, u32::MAX),
+        ]);
+    }
 
     *jump_tuple.ptr_vectors() = ptr_vectors;
-    *jump_tuple.number_of_documents() = flushed.number_of_documents;
-    *jump_tuple.sum_of_document_lengths() = flushed.sum_of_document_lengths;
-    *jump_tuple.width_1_documents() = flushed.width_1_documents;
-    *jump_tuple.width_0_documents() = flushed.width_0_documents;
-    *jump_tuple.depth_documents() = flushed.depth_documents;
-    *jump_tuple.start_documents() = flushed.start_documents;
-    *jump_tuple.free_documents() = flushed.free_documents;
-    *jump_tuple.depth_tokens() = flushed.depth_tokens;
-    *jump_tuple.start_tokens() = flushed.start_tokens;
-    *jump_tuple.free_tokens() = flushed.free_tokens;
-    *jump_tuple.ptr_documents() = flushed.ptr_documents;
-    *jump_tuple.ptr_tokens() = flushed.ptr_tokens;
-    *jump_tuple.ptr_summaries() = flushed.ptr_summaries;
-    *jump_tuple.ptr_blocks() = flushed.ptr_blocks;
+    *jump_tuple.ptr_segments() = ptr_segments;
 
     drop(jump_guard);
 
@@ -308,6 +441,45 @@ pub fn maintain<R: RelationRead + RelationWrite>(
     }
 
     index.vacuum();
+    Ok(())
+}
+
+// Tiered merging: segments are grouped by the magnitude of their live documents,
+// and a tier is merged, together with the sealed vectors, once it collects
+// `MERGE_FACTOR` members, so each document is rewritten a logarithmic number of
+// times. Segments with more deleted than live documents are always rewritten.
+// Only segments in the lowest `tiers` tiers are considered.
+fn select(segments: &[(u32, u32)], number_of_vectors: u32, tiers: u32) -> Vec<bool> {
+    let tier = |n: u64| (n / MERGE_FLOOR).max(1).ilog(MERGE_FACTOR as u64);
+    let live = |(number_of_documents, number_of_deleted_documents): (u32, u32)| {
+        (number_of_documents - number_of_deleted_documents) as u64
+    };
+    let mut selected = segments
+        .iter()
+        .map(|&(n, d)| n < 2 * d && tier(live((n, d))) < tiers)
+        .collect::<Vec<_>>();
+    let mut size = number_of_vectors as u64;
+    for i in 0..segments.len() {
+        if selected[i] {
+            size += live(segments[i]);
+        }
+    }
+    loop {
+        let candidates = (0..segments.len())
+            .filter(|&i| {
+                let t = tier(live(segments[i]));
+                !selected[i] && t <= tier(size) && t < tiers
+            })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() || candidates.len() + ((size != 0) as usize) < MERGE_FACTOR {
+            break;
+        }
+        for i in candidates {
+            selected[i] = true;
+            size += live(segments[i]);
+        }
+    }
+    selected
 }
 
 struct Token {
@@ -325,6 +497,20 @@ struct Block {
     compressed_document_ids: Vec<u8>,
     metadata_term_frequencies: u8,
     compressed_term_frequencies: Vec<u8>,
+    wptr_positions: (u32, u16),
+}
+
+fn document(internal: Vec<Element>, bytes: &[u8], positions: bool) -> Document {
+    let document = Document::new(internal);
+    if positions {
+        let mut bytes = bytes;
+        let positions = (0..document.len())
+            .map(|_| compression::decompress_positions(&mut bytes))
+            .collect();
+        document.with_positions(positions)
+    } else {
+        document
+    }
 }
 
 fn add_document(
@@ -345,6 +531,7 @@ fn add_element(
     relabel_slice: &[u32],
     records_slice: &mut [Record],
     mappings_writer: &mut MappingsWriter,
+    positions: Option<(&mut PositionsWriter, &[u32])>,
     token_id: [u8; WIDTH],
     document_id: u32,
     term_frequency: u32,
@@ -355,8 +542,82 @@ fn add_element(
     };
     {
         let Record(mut length, payload) = records_slice[document_id as usize];
-        length = length.saturating_add(1);
+        length = length.saturating_add(term_frequency);
         records_slice[document_id as usize] = Record(length, payload);
     }
-    mappings_writer.write(Mapping(token_id, document_id, term_frequency));
+    let offset = if let Some((positions_writer, positions)) = positions {
+        positions_writer.write(positions)
+    } else {
+        u64::MAX
+    };
+    mappings_writer.write(Mapping(token_id, document_id, term_frequency, offset));
+}
+
+#[test]
+fn select_tiers() {
+    assert_eq!(select(&[(10, 0); 6], 10, u32::MAX), vec![false; 6]);
+    assert_eq!(select(&[(10, 0); 7], 10, u32::MAX), vec![true; 7]);
+    assert_eq!(select(&[(10, 0); 7], 0, u32::MAX), vec![false; 7]);
+    let mut segments = vec![(1_000_000, 0)];
+    segments.extend([(10, 0); 7]);
+    let mut expected = vec![false];
+    expected.extend([true; 7]);
+    assert_eq!(select(&segments, 10, u32::MAX), expected);
+    assert_eq!(
+        select(&[(1_000_000, 600_000), (10, 0)], 0, u32::MAX),
+        vec![true, false]
+    );
+    // the bottom tier only
+    assert_eq!(
+        select(&[(1_000_000, 600_000), (10, 0)], 0, 1),
+        vec![false, false]
+    );
+    let mut segments = vec![(10_000, 0); 7];
+    segments.extend([(1_000, 0); 7]);
+    assert_eq!(select(&segments, 2_000, u32::MAX), vec![true; 14]);
+    let mut expected = vec![false; 7];
+    expected.extend([true; 7]);
+    assert_eq!(select(&segments, 2_000, 1), expected);
+}
+
+#[test]
+fn seal_merges_bottom_tier() {
+    use crate::testing::*;
+    let index = build(Default::default(), &[document(&[("apple", 1)])]);
+    for i in 1..MERGE_FACTOR {
+        let sealed = document(&[("banana", i as u32)]);
+        crate::insert::insert(&index, &sealed, payload(i)).unwrap();
+        assert!(seal(&index, 1).unwrap());
+        let expected = if i + 1 < MERGE_FACTOR { i + 1 } else { 1 };
+        assert_eq!(segments(&index).len(), expected);
+    }
+    let merged = segments(&index);
+    assert_eq!(merged[0].number_of_documents, MERGE_FACTOR as u32);
+}
+
+#[test]
+fn lengths_agree_with_build() {
+    use crate::testing::*;
+    let corpus = [
+        document(&[("apple", 3), ("banana", 1)]),
+        document(&[("apple", 1), ("cherry", 5), ("durian", 2)]),
+        document(&[("banana", 4)]),
+    ];
+    let index = build(Default::default(), &corpus);
+    crate::bulkdelete::bulkdelete(&index, || (), |x| x != payload(1));
+    maintain(&index).unwrap();
+    let merged = segments(&index);
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].number_of_documents, 1);
+
+    let fresh = build(Default::default(), &corpus[1..2]);
+    let built = segments(&fresh);
+    assert_eq!(
+        merged[0].sum_of_document_lengths,
+        built[0].sum_of_document_lengths
+    );
+    assert_eq!(
+        documents(&index, &merged[0])[0].1,
+        documents(&fresh, &built[0])[0].1
+    );
 }
@@ -0,0 +1,73 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::Opaque;
+use crate::tape::TapeWriter;
+use crate::tuples::{PositionsTuple, WithReader};
+use index::relation::{Page, RelationRead, RelationWrite};
+
+// The positions of a block are split into chunks, which are pushed to the tape
+// one after another, so they are read back by following the tape.
+
+pub fn write<R: RelationWrite>(
+    tape: &mut TapeWriter<'_, R, PositionsTuple>,
+    bytes: &[u8],
+) -> (u32, u16)
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut first = None;
+    let mut remain = bytes;
+    loop {
+        let freespace = tape.freespace();
+        if PositionsTuple::estimate_size_0(remain.len()) <= freespace as usize {
+            let wptr = tape.tape_put(PositionsTuple::_0 {
+                bytes: remain.to_vec(),
+            });
+            return first.unwrap_or(wptr);
+        }
+        if let Some(w) = PositionsTuple::fit_1(freespace) {
+            let (left, right) = remain.split_at(std::cmp::min(w, remain.len()));
+            let wptr = tape.tape_put(PositionsTuple::_1 {
+                bytes: left.to_vec(),
+            });
+            first.get_or_insert(wptr);
+            remain = right;
+        } else {
+            tape.tape_move();
+        }
+    }
+}
+
+pub fn read<R: RelationRead>(index: &R, wptr: (u32, u16)) -> Vec<u8>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut result = Vec::new();
+    let (mut current, mut first) = wptr;
+    loop {
+        let guard = index.read(current);
+        for i in first..=guard.len() {
+            let bytes = guard.get(i).expect("data corruption");
+            let tuple = PositionsTuple::deserialize_ref(bytes);
+            result.extend_from_slice(tuple.bytes());
+            if tuple.last() {
+                return result;
+            }
+        }
+        current = guard.get_opaque().next;
+        first = 1;
+        assert!(current != u32::MAX, "data corruption");
+    }
+}
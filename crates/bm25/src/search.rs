@@ -12,10 +12,10 @@
 //
 // Copyright (c) 2025-2026 TensorChord Inc.
 
-use crate::bm25::Cache;
+use crate::bm25::{Cache, proximity_upper_bounds};
 use crate::tape::TruncatedTapeReader;
 use crate::tuples::*;
-use crate::vector::{Element, Query};
+use crate::vector::{Element, Expression, Phrase, Query};
 use crate::{Opaque, WIDTH, address_documents, address_tokens, compression};
 use always_equal::AlwaysEqual;
 use index::relation::{Page, RelationRead};
@@ -29,16 +29,51 @@ pub fn search<R: RelationRead>(
     index: &R,
     k: NonZero<usize>,
     query: &Query,
-    mut filter: impl FnMut([u16; 3]) -> bool,
+    expression: Option<&Expression>,
+    filter: impl FnMut([u16; 3]) -> bool,
 ) -> Vec<(Reverse<Score>, AlwaysEqual<[u16; 3]>)>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    search_by(index, k, query, expression, filter, false)
+        .into_iter()
+        .map(|(score, AlwaysEqual((payload, _)))| (score, AlwaysEqual(payload)))
+        .collect()
+}
+
+/// Like `search`, but also returns the terms of the query that each result
+/// contains, in order.
+pub fn search_with_terms<R: RelationRead>(
+    index: &R,
+    k: NonZero<usize>,
+    query: &Query,
+    expression: Option<&Expression>,
+    filter: impl FnMut([u16; 3]) -> bool,
+) -> Vec<(Reverse<Score>, AlwaysEqual<([u16; 3], Vec<[u8; WIDTH]>)>)>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    search_by(index, k, query, expression, filter, true)
+}
+
+fn search_by<R: RelationRead>(
+    index: &R,
+    k: NonZero<usize>,
+    query: &Query,
+    expression: Option<&Expression>,
+    mut filter: impl FnMut([u16; 3]) -> bool,
+    with_terms: bool,
+) -> Vec<(Reverse<Score>, AlwaysEqual<([u16; 3], Vec<[u8; WIDTH]>)>)>
 where
     R::Page: Page<Opaque = Opaque>,
 {
     let meta_guard = index.read(0);
     let meta_bytes = meta_guard.get(1).expect("data corruption");
     let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
-    let k1 = meta_tuple.k1();
-    let b = meta_tuple.b();
+    let k1 = query.k1().unwrap_or(meta_tuple.k1());
+    let b = query.b().unwrap_or(meta_tuple.b());
+    let scoring = meta_tuple.scoring();
+    let unit = meta_tuple.unit();
     let ptr_jump = meta_tuple.ptr_jump();
     drop(meta_guard);
 
@@ -46,39 +81,81 @@ where
     let jump_bytes = jump_guard.get(1).expect("data corruption");
     let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
 
-    let sum_of_document_lengths = jump_tuple.sum_of_document_lengths();
-    let number_of_documents = jump_tuple.number_of_documents();
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+
+    let mut number_of_documents = 0_u32;
+    let mut sum_of_document_lengths = 0_u64;
+    for segment in segments.iter() {
+        number_of_documents += segment.number_of_documents;
+        sum_of_document_lengths += segment.sum_of_document_lengths;
+    }
     let avgdl = sum_of_document_lengths as f64 / number_of_documents as f64;
 
     let mut tokens = Vec::new();
-    for &key in query.iter() {
-        let Some((token_guard, token_i)) = address_tokens::read(
-            index,
-            jump_tuple.depth_tokens(),
-            jump_tuple.start_tokens(),
-            key,
-        ) else {
+    for (&key, &weight) in query.iter().zip(query.weights()) {
+        let mut token_number_of_documents = 0_u32;
+        let mut token_sum_of_term_frequencies = 0_u64;
+        let mut postings = Vec::with_capacity(segments.len());
+        for segment in segments.iter() {
+            let Some((token_guard, token_i)) =
+                address_tokens::read(index, segment.depth_tokens, segment.start_tokens, key)
+            else {
+                postings.push(None);
+                continue;
+            };
+            let token_bytes = token_guard.get(token_i).expect("data corruption");
+            let token_tuple = TokenTuple::deserialize_ref(token_bytes);
+            token_number_of_documents += token_tuple.number_of_documents();
+            token_sum_of_term_frequencies += token_tuple.sum_of_term_frequencies();
+            postings.push(Some(Posting {
+                number_of_documents: token_tuple.number_of_documents(),
+                wand_fieldnorm: token_tuple.wand_fieldnorm(),
+                wand_term_frequency: token_tuple.wand_term_frequency(),
+                wptr_summaries: token_tuple.wptr_summaries(),
+            }));
+        }
+        if token_number_of_documents == 0 {
             continue;
-        };
-        let token_bytes = token_guard.get(token_i).expect("data corruption");
-        let token_tuple = TokenTuple::deserialize_ref(token_bytes);
+        }
         tokens.push(Token {
             id: key,
-            number_of_documents: token_tuple.number_of_documents(),
-            wand_fieldnorm: token_tuple.wand_fieldnorm(),
-            wand_term_frequency: token_tuple.wand_term_frequency(),
-            wptr_summaries: token_tuple.wptr_summaries(),
+            required: query.must().binary_search(&key).is_ok(),
+            postings,
             bm25: Cache::new(
                 number_of_documents,
-                token_tuple.number_of_documents(),
+                token_number_of_documents,
+                token_sum_of_term_frequencies,
                 k1,
                 b,
                 avgdl,
+                weight,
+                scoring,
+                unit,
             ),
+            bonus: 0.0,
         });
     }
+    let proximity = query.proximity();
+    if proximity != 0.0 {
+        let limits = tokens.iter().map(|t| t.bm25.limit()).collect::<Vec<_>>();
+        for (token, bonus) in tokens
+            .iter_mut()
+            .zip(proximity_upper_bounds(proximity, &limits))
+        {
+            token.bonus = bonus;
+        }
+    }
 
-    let mut results = Results::<[u16; 3]>::new(k, 0.0);
+    let clauses = query.clauses();
+    let expression = match (expression, clauses.as_ref()) {
+        (Some(l), Some(r)) => Some(Expression::And(Box::new(l.clone()), Box::new(r.clone()))),
+        (l, r) => l.or(r).cloned(),
+    };
+    let expression = expression.as_ref();
+    let minimum_should_match = query.minimum_should_match();
+    let phrases = query.phrases();
+
+    let mut results = Results::<([u16; 3], Vec<[u8; WIDTH]>)>::new(k, 0.0);
 
     {
         let first = jump_tuple.ptr_vectors();
@@ -92,10 +169,25 @@ where
                 let vector_tuple = VectorTuple::deserialize_ref(vector_bytes);
                 match vector_tuple {
                     VectorTupleReader::_2(vector_tuple) => {
-                        state = Some((vector_tuple.fieldnorm(), 0.0));
+                        state = Some((vector_tuple.fieldnorm(), 0.0, Vec::new(), Vec::new()));
                     }
+                    VectorTupleReader::_3(vector_tuple) => {
+                        if let Some((_, _, _, bytes)) = state.as_mut() {
+                            bytes.extend_from_slice(vector_tuple.positions());
+                        } else {
+                            panic!("data corruption");
+                        }
+                    }
+                    VectorTupleReader::_4(_) => {}
                     VectorTupleReader::_1(vector_tuple) => {
-                        if let Some((fieldnorm, result)) = state.as_mut() {
+                        if let Some((fieldnorm, result, keys, _)) = state.as_mut() {
+                            if expression.is_some()
+                                || minimum_should_match != 0
+                                || query.positional()
+                                || with_terms
+                            {
+                                keys.extend(vector_tuple.elements().iter().map(|e| e.key));
+                            }
                             for &Element { key, value } in vector_tuple.elements() {
                                 if let Ok(i) = tokens.binary_search_by_key(&key, |t| t.id) {
                                     let token = &tokens[i];
@@ -108,8 +200,46 @@ where
                         }
                     }
                     VectorTupleReader::_0(vector_tuple) => {
-                        if let Some((fieldnorm, mut result)) = state.take() {
+                        if let Some((fieldnorm, mut result, mut keys, bytes)) = state.take() {
                             if !bool::from(vector_tuple.deleted()) {
+                                if expression.is_some()
+                                    || minimum_should_match != 0
+                                    || query.positional()
+                                    || with_terms
+                                {
+                                    keys.extend(vector_tuple.elements().iter().map(|e| e.key));
+                                }
+                                if keys.iter().filter(|key| query.is_should(key)).count()
+                                    < minimum_should_match
+                                {
+                                    continue;
+                                }
+                                if let Some(expression) = expression {
+                                    let mut contains = |key: &_| keys.binary_search(key).is_ok();
+                                    if expression.evaluate(&mut contains) == Some(false) {
+                                        continue;
+                                    }
+                                }
+                                let lists = if query.positional() {
+                                    let mut bytes = bytes.as_slice();
+                                    keys.iter()
+                                        .map(|_| compression::decompress_positions(&mut bytes))
+                                        .collect::<Vec<_>>()
+                                } else {
+                                    Vec::new()
+                                };
+                                if !phrases.is_empty() {
+                                    let mut positions = |key: &_| match keys.binary_search(key) {
+                                        Ok(i) => lists[i].clone(),
+                                        Err(_) => Vec::new(),
+                                    };
+                                    if !phrases
+                                        .iter()
+                                        .all(|phrase| phrase.matches(query.slop(), &mut positions))
+                                    {
+                                        continue;
+                                    }
+                                }
                                 for &Element { key, value } in vector_tuple.elements() {
                                     if let Ok(i) = tokens.binary_search_by_key(&key, |t| t.id) {
                                         let token = &tokens[i];
@@ -117,10 +247,29 @@ where
                                         result += token.bm25.evaluate(fieldnorm, term_frequency);
                                     }
                                 }
+                                if proximity != 0.0 {
+                                    let terms = tokens
+                                        .iter()
+                                        .filter_map(|t| {
+                                            let i = keys.binary_search(&t.id).ok()?;
+                                            Some((t.bm25.limit(), lists[i].as_slice()))
+                                        })
+                                        .collect::<Vec<_>>();
+                                    result += crate::bm25::proximity(proximity, &terms);
+                                }
                                 if results.threshold() < result {
                                     let payload = vector_tuple.payload();
                                     if filter(payload) {
-                                        results.push(result, payload);
+                                        let matched = if with_terms {
+                                            tokens
+                                                .iter()
+                                                .map(|t| t.id)
+                                                .filter(|key| keys.binary_search(key).is_ok())
+                                                .collect()
+                                        } else {
+                                            Vec::new()
+                                        };
+                                        results.push(result, (payload, matched));
                                     }
                                 }
                             }
@@ -134,34 +283,169 @@ where
         }
     }
 
-    let mut cursors = Vec::new();
-    for token in tokens {
-        cursors.push(Box::new(Cursor::new(
+    // a required token that is in no segment excludes every document in segments
+    let satisfiable = query
+        .must()
+        .iter()
+        .all(|key| tokens.binary_search_by_key(key, |t| t.id).is_ok());
+
+    for (i, segment) in segments.iter().enumerate() {
+        if !satisfiable || tokens.iter().any(|t| t.required && t.postings[i].is_none()) {
+            continue;
+        }
+        let should = tokens
+            .iter()
+            .filter(|t| !t.required && t.postings[i].is_some())
+            .count();
+        if should < minimum_should_match {
+            continue;
+        }
+        let mut cursors = Vec::new();
+        for token in tokens.iter() {
+            if let Some(posting) = token.postings[i].as_ref() {
+                cursors.push(Box::new(Cursor::new(
+                    index,
+                    posting.number_of_documents,
+                    posting.wand_fieldnorm,
+                    posting.wand_term_frequency,
+                    posting.wptr_summaries,
+                    token.id,
+                    &token.bm25,
+                    token.bonus,
+                    token.required,
+                )));
+            }
+        }
+        let mut matcher = expression.map(|expression| Matcher::new(index, segment, expression));
+        let mut locator = (!phrases.is_empty()).then(|| Locator::new(index, segment, phrases));
+        let mut accept = |document_id, payload| {
+            if let Some(matcher) = matcher.as_mut() {
+                if matcher.evaluate(index, document_id) == Some(false) {
+                    return false;
+                }
+            }
+            if let Some(locator) = locator.as_mut() {
+                if !locator.evaluate(index, document_id, phrases, query.slop()) {
+                    return false;
+                }
+            }
+            filter(payload)
+        };
+        wand(
             index,
-            token.number_of_documents,
-            token.wand_fieldnorm,
-            token.wand_term_frequency,
-            token.wptr_summaries,
-            token.bm25,
-        )));
+            segment,
+            cursors,
+            minimum_should_match,
+            proximity,
+            with_terms,
+            &mut results,
+            &mut accept,
+        );
     }
 
-    let mut tail = Vec::<Box<Cursor>>::new();
+    results.into_sorted_vec()
+}
+
+pub fn matching<R: RelationRead>(index: &R, expression: &Expression) -> Vec<[u16; 3]>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    let ptr_jump = meta_tuple.ptr_jump();
+    drop(meta_guard);
+
+    let jump_guard = index.read(ptr_jump);
+    let jump_bytes = jump_guard.get(1).expect("data corruption");
+    let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
+
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+
+    // if a document without any token is matched, every document is a candidate
+    let negative = expression.evaluate(&mut |_| false) != Some(false);
+
+    let mut results = Vec::new();
+
+    crate::vectors::read(index, jump_tuple.ptr_vectors(), |document| {
+        let elements = document.elements;
+        let mut contains = |key: &_| elements.binary_search_by_key(key, |e| e.key).is_ok();
+        if expression.evaluate(&mut contains) != Some(false) {
+            results.push(document.payload);
+        }
+    });
+
+    for segment in segments.iter() {
+        let mut matcher = Matcher::new(index, segment, expression);
+        let candidates = if negative {
+            (0..segment.number_of_documents).collect::<Vec<_>>()
+        } else {
+            let mut candidates = Vec::new();
+            for membership in matcher.tokens.iter().filter_map(|(_, x)| x.as_ref()) {
+                membership.document_ids(index, &mut candidates);
+            }
+            candidates.sort_unstable();
+            candidates.dedup();
+            candidates
+        };
+        for document_id in candidates {
+            if matcher.evaluate(index, document_id) == Some(false) {
+                continue;
+            }
+            let (document_guard, document_i) = address_documents::read(
+                index,
+                segment.width_1_documents,
+                segment.width_0_documents,
+                segment.depth_documents,
+                segment.start_documents,
+                document_id,
+            )
+            .expect("data corruption");
+            let document_bytes = document_guard.get(document_i).expect("data corruption");
+            let document_tuple = DocumentTuple::deserialize_ref(document_bytes);
+            if !bool::from(document_tuple.deleted()) {
+                results.push(document_tuple.payload());
+            }
+        }
+    }
+
+    results
+}
+
+fn wand<R: RelationRead>(
+    index: &R,
+    segment: &SegmentTuple,
+    cursors: Vec<Box<Cursor<'_>>>,
+    minimum_should_match: usize,
+    proximity: f64,
+    with_terms: bool,
+    results: &mut Results<([u16; 3], Vec<[u8; WIDTH]>)>,
+    accept: &mut impl FnMut(u32, [u16; 3]) -> bool,
+) where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut tail = Vec::<Box<Cursor<'_>>>::new();
     let mut head = BinaryHeap::from(cursors);
     'main: loop {
         let lead = 'lead: {
             let mut sum = 0.0f64;
+            let mut should = 0_usize;
             for cursor in tail.iter() {
                 sum += cursor.token_upper_bound();
+                should += !cursor.required() as usize;
             }
             while let Some(cursor) = head.pop() {
                 if cursor.document_id() == u32::MAX {
                     break 'main;
                 }
-                if results.threshold() < sum + cursor.token_upper_bound() {
+                // a document before the pivot contains no term outside of `tail`
+                if results.threshold() < sum + cursor.token_upper_bound()
+                    && minimum_should_match <= should + !cursor.required() as usize
+                {
                     break 'lead cursor;
                 } else {
                     sum += cursor.token_upper_bound();
+                    should += !cursor.required() as usize;
                     tail.push(cursor);
                 }
             }
@@ -174,6 +458,23 @@ where
         {
             lead.push(cursor);
         }
+        // no document before any required cursor can be accepted
+        let floor = head
+            .iter()
+            .filter(|cursor| cursor.required())
+            .map(|cursor| cursor.document_id())
+            .max()
+            .unwrap_or(document_id);
+        if document_id < floor {
+            if floor == u32::MAX {
+                break 'main;
+            }
+            for mut cursor in lead {
+                cursor.seek(index, floor);
+                head.push(cursor);
+            }
+            continue 'main;
+        }
         {
             let mut failures = tail.extract_if(.., |cursor| {
                 cursor.seek_block(index, document_id);
@@ -200,7 +501,10 @@ where
             }
             result
         };
-        if results.threshold() < sum_of_block_upper_bounds {
+        let should = chain(tail.iter(), lead.iter())
+            .filter(|cursor| !cursor.required())
+            .count();
+        if results.threshold() < sum_of_block_upper_bounds && minimum_should_match <= should {
             {
                 let mut failures = tail.extract_if(.., |cursor| {
                     cursor.seek(index, document_id);
@@ -216,10 +520,10 @@ where
             };
             let (document_guard, document_i) = address_documents::read(
                 index,
-                jump_tuple.width_1_documents(),
-                jump_tuple.width_0_documents(),
-                jump_tuple.depth_documents(),
-                jump_tuple.start_documents(),
+                segment.width_1_documents,
+                segment.width_0_documents,
+                segment.depth_documents,
+                segment.start_documents,
                 document_id,
             )
             .expect("data corruption");
@@ -227,13 +531,32 @@ where
             let document_tuple = DocumentTuple::deserialize_ref(document_bytes);
             let fieldnorm = document_tuple.fieldnorm();
             let payload = document_tuple.payload();
-            if filter(payload) {
+            if accept(document_id, payload) {
                 let mut result = 0.0;
                 for cursor in chain(tail.iter_mut(), lead.iter_mut()) {
                     let term_frequency = cursor.get(index);
                     result += cursor.bm25().evaluate(fieldnorm, term_frequency);
                 }
-                results.push(result, payload);
+                if proximity != 0.0 {
+                    let lists = chain(tail.iter_mut(), lead.iter_mut())
+                        .map(|cursor| (cursor.bm25().limit(), cursor.positions(index)))
+                        .collect::<Vec<_>>();
+                    let terms = lists
+                        .iter()
+                        .map(|(limit, positions)| (*limit, positions.as_slice()))
+                        .collect::<Vec<_>>();
+                    result += crate::bm25::proximity(proximity, &terms);
+                }
+                let matched = if with_terms {
+                    let mut matched = chain(tail.iter(), lead.iter())
+                        .map(|cursor| cursor.id())
+                        .collect::<Vec<_>>();
+                    matched.sort_unstable();
+                    matched
+                } else {
+                    Vec::new()
+                };
+                results.push(result, (payload, matched));
             }
             for mut cursor in chain(tail, lead) {
                 cursor.seek(index, 1 + document_id);
@@ -278,7 +601,6 @@ where
             }
         }
     }
-    results.into_sorted_vec()
 }
 
 struct Results<T> {
@@ -313,8 +635,11 @@ impl<T> Results<T> {
     }
 }
 
-struct Cursor {
-    bm25: Cache,
+struct Cursor<'a> {
+    id: [u8; WIDTH],
+    bm25: &'a Cache,
+    bonus: f64,
+    required: bool,
     token_upper_bound: f64,
 
     document_id: u32,
@@ -326,61 +651,55 @@ struct Cursor {
     block_upper_bound: f64,
     filled: bool,
     block: Block,
+    positions: Option<Vec<u8>>,
 }
 
-impl PartialEq for Cursor {
+impl PartialEq for Cursor<'_> {
     fn eq(&self, other: &Self) -> bool {
         PartialEq::eq(&other.document_id, &self.document_id)
     }
 }
 
-impl Eq for Cursor {}
+impl Eq for Cursor<'_> {}
 
-impl PartialOrd for Cursor {
+impl PartialOrd for Cursor<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(Ord::cmp(&other.document_id, &self.document_id))
     }
 }
 
-impl Ord for Cursor {
+impl Ord for Cursor<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
         Ord::cmp(&other.document_id, &self.document_id)
     }
 }
 
-impl Cursor {
+impl<'a> Cursor<'a> {
     fn new<R: RelationRead>(
         index: &R,
         token_number_of_documents: u32,
         token_wand_fieldnorm: u8,
         token_wand_term_frequency: u32,
         wptr_summaries: (u32, u16),
-        bm25: Cache,
+        id: [u8; WIDTH],
+        bm25: &'a Cache,
+        bonus: f64,
+        required: bool,
     ) -> Self
     where
         R::Page: Page<Opaque = Opaque>,
     {
-        let token_upper_bound = bm25.evaluate(token_wand_fieldnorm, token_wand_term_frequency);
-        let mut incoming = TruncatedTapeReader::new(
-            index,
-            wptr_summaries,
-            |bytes| {
-                let summary_tuple = SummaryTuple::deserialize_ref(bytes);
-                Summary {
-                    min_document_id: summary_tuple.min_document_id(),
-                    max_document_id: summary_tuple.max_document_id(),
-                    number_of_documents: summary_tuple.number_of_documents(),
-                    wand_fieldnorm: summary_tuple.wand_fieldnorm(),
-                    wand_term_frequency: summary_tuple.wand_term_frequency(),
-                    wptr_block: summary_tuple.wptr_block().into_inner(),
-                }
-            },
-            token_number_of_documents.div_ceil(128),
-        );
+        let token_upper_bound =
+            bm25.evaluate(token_wand_fieldnorm, token_wand_term_frequency) + bonus;
+        let mut incoming = summaries(index, token_number_of_documents, wptr_summaries);
         let summary = next_summary(&mut incoming, index);
-        let block_upper_bound = bm25.evaluate(summary.wand_fieldnorm, summary.wand_term_frequency);
+        let block_upper_bound =
+            bm25.evaluate(summary.wand_fieldnorm, summary.wand_term_frequency) + bonus;
         Cursor {
+            id,
             bm25,
+            bonus,
+            required,
             token_upper_bound,
             document_id: summary.min_document_id,
             position_in_block: 0,
@@ -390,12 +709,20 @@ impl Cursor {
             block: Block {
                 document_ids: compression::Decompressed::new(),
                 term_frequencies: compression::Decompressed::new(),
+                wptr_positions: (u32::MAX, 0),
             },
+            positions: None,
             incoming,
         }
     }
-    fn bm25(&self) -> &Cache {
-        &self.bm25
+    fn id(&self) -> [u8; WIDTH] {
+        self.id
+    }
+    fn bm25(&self) -> &'a Cache {
+        self.bm25
+    }
+    fn required(&self) -> bool {
+        self.required
     }
     fn token_upper_bound(&self) -> f64 {
         self.token_upper_bound
@@ -426,8 +753,9 @@ impl Cursor {
         self.block_upper_bound = self.bm25().evaluate(
             self.summary.wand_fieldnorm,
             self.summary.wand_term_frequency,
-        );
+        ) + self.bonus;
         self.filled = false;
+        self.positions = None;
     }
     fn seek<R: RelationRead>(&mut self, index: &R, document_id: u32)
     where
@@ -479,6 +807,50 @@ impl Cursor {
         }
         self.block.term_frequencies.as_slice()[self.position_in_block as usize]
     }
+    fn positions<R: RelationRead>(&mut self, index: &R) -> Vec<u32>
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        if !self.filled {
+            fill_block(
+                &mut self.block,
+                index,
+                self.summary.min_document_id,
+                self.summary.wptr_block,
+            );
+            self.filled = true;
+        }
+        let bytes = self
+            .positions
+            .get_or_insert_with(|| crate::positions::read(index, self.block.wptr_positions));
+        nth_positions(bytes, self.position_in_block as usize)
+    }
+}
+
+fn summaries<R: RelationRead>(
+    index: &R,
+    token_number_of_documents: u32,
+    wptr_summaries: (u32, u16),
+) -> TruncatedTapeReader<Summary>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    TruncatedTapeReader::new(
+        index,
+        wptr_summaries,
+        |bytes| {
+            let summary_tuple = SummaryTuple::deserialize_ref(bytes);
+            Summary {
+                min_document_id: summary_tuple.min_document_id(),
+                max_document_id: summary_tuple.max_document_id(),
+                number_of_documents: summary_tuple.number_of_documents(),
+                wand_fieldnorm: summary_tuple.wand_fieldnorm(),
+                wand_term_frequency: summary_tuple.wand_term_frequency(),
+                wptr_block: summary_tuple.wptr_block().into_inner(),
+            }
+        },
+        token_number_of_documents.div_ceil(128),
+    )
 }
 
 fn next_summary<R: RelationRead>(incoming: &mut TruncatedTapeReader<Summary>, index: &R) -> Summary
@@ -515,15 +887,250 @@ fn fill_block<R: RelationRead>(
         block_tuple.compressed_term_frequencies(),
         &mut block.term_frequencies,
     );
+    block.wptr_positions = block_tuple.wptr_positions().into_inner();
+}
+
+fn nth_positions(bytes: &[u8], n: usize) -> Vec<u32> {
+    let mut bytes = bytes;
+    for _ in 0..n {
+        compression::decompress_positions(&mut bytes);
+    }
+    compression::decompress_positions(&mut bytes)
+}
+
+struct Matcher<'a> {
+    expression: &'a Expression,
+    tokens: Vec<([u8; WIDTH], Option<Membership>)>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new<R: RelationRead>(index: &R, segment: &SegmentTuple, expression: &'a Expression) -> Self
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        let mut tokens = Vec::new();
+        for key in expression.tokens() {
+            let membership =
+                address_tokens::read(index, segment.depth_tokens, segment.start_tokens, key).map(
+                    |(token_guard, token_i)| {
+                        let token_bytes = token_guard.get(token_i).expect("data corruption");
+                        let token_tuple = TokenTuple::deserialize_ref(token_bytes);
+                        Membership::new(
+                            index,
+                            token_tuple.number_of_documents(),
+                            token_tuple.wptr_summaries(),
+                        )
+                    },
+                );
+            tokens.push((key, membership));
+        }
+        Self { expression, tokens }
+    }
+    // `document_id` must be non-decreasing between calls.
+    fn evaluate<R: RelationRead>(&mut self, index: &R, document_id: u32) -> Option<bool>
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        let tokens = &mut self.tokens;
+        self.expression.evaluate(&mut |key| {
+            let Ok(i) = tokens.binary_search_by_key(key, |(key, _)| *key) else {
+                unreachable!()
+            };
+            if let Some(membership) = tokens[i].1.as_mut() {
+                membership.contains(index, document_id)
+            } else {
+                false
+            }
+        })
+    }
+}
+
+struct Locator {
+    tokens: Vec<([u8; WIDTH], Option<Membership>)>,
+}
+
+impl Locator {
+    fn new<R: RelationRead>(index: &R, segment: &SegmentTuple, phrases: &[Phrase]) -> Self
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        let mut keys = phrases.iter().flat_map(|x| x.keys()).collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys.dedup();
+        let mut tokens = Vec::new();
+        for key in keys {
+            let membership =
+                address_tokens::read(index, segment.depth_tokens, segment.start_tokens, key).map(
+                    |(token_guard, token_i)| {
+                        let token_bytes = token_guard.get(token_i).expect("data corruption");
+                        let token_tuple = TokenTuple::deserialize_ref(token_bytes);
+                        Membership::new(
+                            index,
+                            token_tuple.number_of_documents(),
+                            token_tuple.wptr_summaries(),
+                        )
+                    },
+                );
+            tokens.push((key, membership));
+        }
+        Self { tokens }
+    }
+    // `document_id` must be non-decreasing between calls.
+    fn evaluate<R: RelationRead>(
+        &mut self,
+        index: &R,
+        document_id: u32,
+        phrases: &[Phrase],
+        slop: u32,
+    ) -> bool
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        let tokens = &mut self.tokens;
+        let mut positions = |key: &_| {
+            let Ok(i) = tokens.binary_search_by_key(key, |(key, _)| *key) else {
+                unreachable!()
+            };
+            if let Some(membership) = tokens[i].1.as_mut() {
+                membership.positions(index, document_id)
+            } else {
+                Vec::new()
+            }
+        };
+        phrases
+            .iter()
+            .all(|phrase| phrase.matches(slop, &mut positions))
+    }
+}
+
+struct Membership {
+    number_of_documents: u32,
+    wptr_summaries: (u32, u16),
+    incoming: TruncatedTapeReader<Summary>,
+    summary: Summary,
+    filled: bool,
+    block: Block,
+    positions: Option<Vec<u8>>,
+}
+
+impl Membership {
+    fn new<R: RelationRead>(index: &R, number_of_documents: u32, wptr_summaries: (u32, u16)) -> Self
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        let mut incoming = summaries(index, number_of_documents, wptr_summaries);
+        let summary = next_summary(&mut incoming, index);
+        Self {
+            number_of_documents,
+            wptr_summaries,
+            incoming,
+            summary,
+            filled: false,
+            block: Block {
+                document_ids: compression::Decompressed::new(),
+                term_frequencies: compression::Decompressed::new(),
+                wptr_positions: (u32::MAX, 0),
+            },
+            positions: None,
+        }
+    }
+    fn contains<R: RelationRead>(&mut self, index: &R, document_id: u32) -> bool
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        while self.summary.max_document_id < document_id {
+            self.summary = next_summary(&mut self.incoming, index);
+            self.filled = false;
+            self.positions = None;
+        }
+        if document_id < self.summary.min_document_id {
+            return false;
+        }
+        if document_id == self.summary.min_document_id
+            || document_id == self.summary.max_document_id
+        {
+            return true;
+        }
+        if !self.filled {
+            fill_block(
+                &mut self.block,
+                index,
+                self.summary.min_document_id,
+                self.summary.wptr_block,
+            );
+            self.filled = true;
+        }
+        self.block
+            .document_ids
+            .as_slice()
+            .binary_search(&document_id)
+            .is_ok()
+    }
+    // `document_id` must be non-decreasing between calls.
+    fn positions<R: RelationRead>(&mut self, index: &R, document_id: u32) -> Vec<u32>
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        if !self.contains(index, document_id) {
+            return Vec::new();
+        }
+        if !self.filled {
+            fill_block(
+                &mut self.block,
+                index,
+                self.summary.min_document_id,
+                self.summary.wptr_block,
+            );
+            self.filled = true;
+        }
+        let Ok(i) = self
+            .block
+            .document_ids
+            .as_slice()
+            .binary_search(&document_id)
+        else {
+            panic!("data corruption");
+        };
+        let bytes = self
+            .positions
+            .get_or_insert_with(|| crate::positions::read(index, self.block.wptr_positions));
+        nth_positions(bytes, i)
+    }
+    fn document_ids<R: RelationRead>(&self, index: &R, result: &mut Vec<u32>)
+    where
+        R::Page: Page<Opaque = Opaque>,
+    {
+        let mut incoming = summaries(index, self.number_of_documents, self.wptr_summaries);
+        let mut block = Block {
+            document_ids: compression::Decompressed::new(),
+            term_frequencies: compression::Decompressed::new(),
+            wptr_positions: (u32::MAX, 0),
+        };
+        while let Some(summary) = incoming.next(index) {
+            fill_block(
+                &mut block,
+                index,
+                summary.min_document_id,
+                summary.wptr_block,
+            );
+            result.extend_from_slice(block.document_ids.as_slice());
+        }
+    }
 }
 
 struct Token {
     id: [u8; WIDTH],
+    required: bool,
+    postings: Vec<Option<Posting>>,
+    bm25: Cache,
+    bonus: f64,
+}
+
+struct Posting {
     number_of_documents: u32,
     wand_fieldnorm: u8,
     wand_term_frequency: u32,
     wptr_summaries: (u32, u16),
-    bm25: Cache,
 }
 
 struct Summary {
@@ -538,6 +1145,7 @@ struct Summary {
 struct Block {
     document_ids: compression::Decompressed,
     term_frequencies: compression::Decompressed,
+    wptr_positions: (u32, u16),
 }
 
 // Emulate unstable library feature `binary_heap_pop_if`.
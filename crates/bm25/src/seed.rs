@@ -27,3 +27,26 @@ pub fn seed<R: RelationRead>(index: &R) -> [u8; 32] {
     let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
     meta_tuple.seed()
 }
+
+/// Returns the multipliers of positions under each label, in units of stored
+/// term frequency.
+pub fn multipliers<R: RelationRead>(index: &R) -> [u32; 4] {
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    crate::bm25::multipliers(&meta_tuple.weights())
+}
+
+pub fn positions<R: RelationRead>(index: &R) -> bool {
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    meta_tuple.positions()
+}
+
+pub fn dictionary<R: RelationRead>(index: &R) -> bool {
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    meta_tuple.dictionary()
+}
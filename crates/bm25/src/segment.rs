@@ -13,6 +13,7 @@
 // Copyright (c) 2025-2026 TensorChord Inc.
 
 use crate::WIDTH;
+use crate::io::PositionsReader;
 use std::cmp::Ordering;
 use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
@@ -20,9 +21,11 @@ use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 #[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
 pub struct Record(pub u32, pub [u16; 3]);
 
+/// A posting of a term in a document, with the offset of its positions in the
+/// positions files or `u64::MAX` if positions are not stored.
 #[repr(C)]
 #[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
-pub struct Mapping(pub [u8; WIDTH], pub u32, pub u32);
+pub struct Mapping(pub [u8; WIDTH], pub u32, pub u32, pub u64);
 
 impl PartialEq for Mapping {
     fn eq(&self, other: &Self) -> bool {
@@ -47,4 +50,5 @@ impl Ord for Mapping {
 pub struct Segment<R, M> {
     pub records: R,
     pub mappings: M,
+    pub positions: Option<PositionsReader>,
 }
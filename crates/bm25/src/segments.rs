@@ -0,0 +1,52 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::Opaque;
+use crate::tape::TapeReader;
+use crate::tuples::{SegmentTuple, WithReader};
+use index::relation::{Page, RelationRead};
+
+pub fn read<R: RelationRead>(index: &R, ptr_segments: u32) -> Vec<SegmentTuple>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut tape = TapeReader::new(ptr_segments, deserialize);
+    let mut segments = Vec::new();
+    while let Some(segment) = tape.next(index) {
+        segments.push(segment);
+    }
+    segments
+}
+
+pub fn deserialize(bytes: &[u8]) -> SegmentTuple {
+    let segment_tuple = SegmentTuple::deserialize_ref(bytes);
+    SegmentTuple {
+        number_of_documents: segment_tuple.number_of_documents(),
+        number_of_deleted_documents: segment_tuple.number_of_deleted_documents(),
+        sum_of_document_lengths: segment_tuple.sum_of_document_lengths(),
+        width_1_documents: segment_tuple.width_1_documents(),
+        width_0_documents: segment_tuple.width_0_documents(),
+        depth_documents: segment_tuple.depth_documents(),
+        start_documents: segment_tuple.start_documents(),
+        free_documents: segment_tuple.free_documents(),
+        depth_tokens: segment_tuple.depth_tokens(),
+        start_tokens: segment_tuple.start_tokens(),
+        free_tokens: segment_tuple.free_tokens(),
+        ptr_documents: segment_tuple.ptr_documents(),
+        ptr_tokens: segment_tuple.ptr_tokens(),
+        ptr_summaries: segment_tuple.ptr_summaries(),
+        ptr_blocks: segment_tuple.ptr_blocks(),
+        ptr_positions: segment_tuple.ptr_positions(),
+    }
+}
@@ -0,0 +1,207 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::bm25::idf;
+use crate::tuples::*;
+use crate::{Opaque, WIDTH, address_tokens};
+use index::relation::{Page, RelationRead};
+
+pub struct IndexStats {
+    pub number_of_segments: u32,
+    /// Documents of segments, which are the documents that scoring counts,
+    /// including deleted ones that are not compacted yet.
+    pub number_of_documents: u32,
+    /// Deleted documents of segments and deleted documents that are not
+    /// sealed yet.
+    pub number_of_deleted_documents: u32,
+    /// Lengths are weighted by labels as term frequencies are.
+    pub sum_of_document_lengths: f64,
+    pub avgdl: f64,
+    /// Live documents that are not sealed yet.
+    pub number_of_unsealed_documents: u32,
+    pub pages: Pages,
+}
+
+/// Numbers of pages of each region of the index, found by following the
+/// pointers of the jump tuple and of segments.
+pub struct Pages {
+    pub vectors: u32,
+    pub segments: u32,
+    pub terms: u32,
+    pub documents: u32,
+    pub tokens: u32,
+    pub summaries: u32,
+    pub blocks: u32,
+    pub positions: u32,
+    pub addresses: u32,
+}
+
+pub struct TermStats {
+    /// Documents of segments containing the term.
+    pub document_frequency: u32,
+    pub sum_of_term_frequencies: f64,
+    pub idf: f64,
+    /// The maximum over segments of the term frequency kept for wand.
+    pub max_term_frequency: f64,
+    pub number_of_blocks: u32,
+}
+
+pub fn index_stats<R: RelationRead>(index: &R) -> IndexStats
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    let ptr_jump = meta_tuple.ptr_jump();
+    let dictionary = meta_tuple.dictionary();
+    let unit = meta_tuple.unit();
+    drop(meta_guard);
+
+    let jump_guard = index.read(ptr_jump);
+    let jump_bytes = jump_guard.get(1).expect("data corruption");
+    let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
+
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+
+    let mut number_of_documents = 0_u32;
+    let mut number_of_deleted_documents = 0_u32;
+    let mut sum_of_document_lengths = 0_u64;
+    let mut pages = Pages {
+        vectors: count(index, jump_tuple.ptr_vectors()),
+        segments: count(index, jump_tuple.ptr_segments()),
+        terms: if dictionary {
+            count(index, jump_tuple.ptr_terms()) + count(index, jump_tuple.ptr_strings())
+        } else {
+            0
+        },
+        documents: 0,
+        tokens: 0,
+        summaries: 0,
+        blocks: 0,
+        positions: 0,
+        addresses: if dictionary {
+            count(index, jump_tuple.free_terms()) + count(index, jump_tuple.free_strings())
+        } else {
+            0
+        },
+    };
+    for segment in segments.iter() {
+        number_of_documents += segment.number_of_documents;
+        number_of_deleted_documents += segment.number_of_deleted_documents;
+        sum_of_document_lengths += segment.sum_of_document_lengths;
+        pages.documents += count(index, segment.ptr_documents);
+        pages.tokens += count(index, segment.ptr_tokens);
+        pages.summaries += count(index, segment.ptr_summaries);
+        pages.blocks += count(index, segment.ptr_blocks);
+        pages.positions += count(index, segment.ptr_positions);
+        pages.addresses += count(index, segment.free_documents);
+        pages.addresses += count(index, segment.free_tokens);
+    }
+    let avgdl = if number_of_documents != 0 {
+        sum_of_document_lengths as f64 * unit / number_of_documents as f64
+    } else {
+        0.0
+    };
+
+    let mut number_of_unsealed_documents = 0_u32;
+    let mut current = jump_tuple.ptr_vectors();
+    while current != u32::MAX {
+        let vector_guard = index.read(current);
+        for i in 1..=vector_guard.len() {
+            let vector_bytes = vector_guard.get(i).expect("data corruption");
+            if let VectorTupleReader::_0(vector_tuple) = VectorTuple::deserialize_ref(vector_bytes)
+            {
+                if bool::from(vector_tuple.deleted()) {
+                    number_of_deleted_documents += 1;
+                } else {
+                    number_of_unsealed_documents += 1;
+                }
+            }
+        }
+        current = vector_guard.get_opaque().next;
+    }
+
+    IndexStats {
+        number_of_segments: segments.len() as u32,
+        number_of_documents,
+        number_of_deleted_documents,
+        sum_of_document_lengths: sum_of_document_lengths as f64 * unit,
+        avgdl,
+        number_of_unsealed_documents,
+        pages,
+    }
+}
+
+/// Returns the statistics of a key in segments, reading every block of its
+/// postings for the maximum term frequency.
+pub fn term_stats<R: RelationRead>(index: &R, key: [u8; WIDTH]) -> TermStats
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let meta_guard = index.read(0);
+    let meta_bytes = meta_guard.get(1).expect("data corruption");
+    let meta_tuple = MetaTuple::deserialize_ref(meta_bytes);
+    let ptr_jump = meta_tuple.ptr_jump();
+    let unit = meta_tuple.unit();
+    drop(meta_guard);
+
+    let jump_guard = index.read(ptr_jump);
+    let jump_bytes = jump_guard.get(1).expect("data corruption");
+    let jump_tuple = JumpTuple::deserialize_ref(jump_bytes);
+
+    let segments = crate::segments::read(index, jump_tuple.ptr_segments());
+
+    let mut number_of_documents = 0_u32;
+    let mut document_frequency = 0_u32;
+    let mut sum_of_term_frequencies = 0_u64;
+    let mut max_term_frequency = 0_u32;
+    let mut number_of_blocks = 0_u32;
+    for segment in segments.iter() {
+        number_of_documents += segment.number_of_documents;
+        let Some((token_guard, token_i)) =
+            address_tokens::read(index, segment.depth_tokens, segment.start_tokens, key)
+        else {
+            continue;
+        };
+        let token_bytes = token_guard.get(token_i).expect("data corruption");
+        let token_tuple = TokenTuple::deserialize_ref(token_bytes);
+        let token_number_of_documents = token_tuple.number_of_documents();
+        document_frequency += token_number_of_documents;
+        sum_of_term_frequencies += token_tuple.sum_of_term_frequencies();
+        max_term_frequency = max_term_frequency.max(token_tuple.wand_term_frequency());
+        number_of_blocks += token_number_of_documents.div_ceil(128);
+    }
+
+    TermStats {
+        document_frequency,
+        sum_of_term_frequencies: sum_of_term_frequencies as f64 * unit,
+        idf: idf(number_of_documents, document_frequency),
+        max_term_frequency: max_term_frequency as f64 * unit,
+        number_of_blocks,
+    }
+}
+
+fn count<R: RelationRead>(index: &R, first: u32) -> u32
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut result = 0_u32;
+    let mut current = first;
+    while current != u32::MAX {
+        result += 1;
+        current = index.read(current).get_opaque().next;
+    }
+    result
+}
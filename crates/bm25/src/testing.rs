@@ -0,0 +1,394 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::Opaque;
+use crate::dictionary::{Collision, Dictionary};
+use crate::tape::TapeReader;
+use crate::tuples::*;
+use crate::types::Bm25IndexOptions;
+use crate::vector::{Document, Element, intern};
+use index::relation::{
+    Opaque as _, Page, PageGuard, Relation, RelationLength, RelationRead, RelationReadTypes,
+    RelationWrite, RelationWriteTypes,
+};
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use zerocopy::IntoBytes;
+
+// An in-memory relation with the page layout of PostgreSQL, so that tuples
+// are placed as they are in a real index. Guards work on copies of pages and
+// write guards store them back on drop, unless the thread is panicking.
+
+pub const SEED: [u8; 32] = [7; 32];
+
+const BLCKSZ: usize = 8192;
+const HEADER: usize = 24;
+const ITEM_ID: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct MemoryPage {
+    opaque: Opaque,
+    items: Vec<Option<(Vec<u64>, usize)>>,
+}
+
+impl MemoryPage {
+    fn new(opaque: Opaque) -> Self {
+        Self {
+            opaque,
+            items: Vec::new(),
+        }
+    }
+    fn upper(&self) -> usize {
+        let used = self
+            .items
+            .iter()
+            .flatten()
+            .map(|(_, len)| len.next_multiple_of(8))
+            .sum::<usize>();
+        BLCKSZ - size_of::<Opaque>() - used
+    }
+    fn lower(&self) -> usize {
+        HEADER + ITEM_ID * self.items.len()
+    }
+}
+
+impl Page for MemoryPage {
+    type Opaque = Opaque;
+    fn get_opaque(&self) -> &Opaque {
+        &self.opaque
+    }
+    fn get_opaque_mut(&mut self) -> &mut Opaque {
+        &mut self.opaque
+    }
+    fn len(&self) -> u16 {
+        self.items.len() as u16
+    }
+    fn get(&self, i: u16) -> Option<&[u8]> {
+        let (data, len) = self.items.get((i as usize).checked_sub(1)?)?.as_ref()?;
+        Some(&data.as_bytes()[..*len])
+    }
+    fn get_mut(&mut self, i: u16) -> Option<&mut [u8]> {
+        let (data, len) = self.items.get_mut((i as usize).checked_sub(1)?)?.as_mut()?;
+        Some(&mut data.as_mut_bytes()[..*len])
+    }
+    fn alloc(&mut self, data: &[u8]) -> Option<u16> {
+        if self.lower() + ITEM_ID + data.len().next_multiple_of(8) > self.upper() {
+            return None;
+        }
+        let mut buffer = vec![0_u64; data.len().div_ceil(8)];
+        buffer.as_mut_bytes()[..data.len()].copy_from_slice(data);
+        self.items.push(Some((buffer, data.len())));
+        Some(self.items.len() as u16)
+    }
+    fn free(&mut self, i: u16) {
+        self.items[i as usize - 1] = None;
+        while let Some(None) = self.items.last() {
+            self.items.pop();
+        }
+    }
+    fn freespace(&self) -> u16 {
+        (self.upper() - self.lower()).saturating_sub(ITEM_ID) as u16
+    }
+    fn clear(&mut self, opaque: Opaque) {
+        *self = Self::new(opaque);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryRelation {
+    pages: RefCell<Vec<MemoryPage>>,
+    free: RefCell<Vec<u32>>,
+}
+
+impl MemoryRelation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Modifies a page in place, bypassing locks, to corrupt the index.
+    pub fn corrupt(&self, id: u32, f: impl FnOnce(&mut MemoryPage)) {
+        f(&mut self.pages.borrow_mut()[id as usize]);
+    }
+}
+
+pub struct MemoryReadGuard {
+    id: u32,
+    page: MemoryPage,
+}
+
+impl PageGuard for MemoryReadGuard {
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Deref for MemoryReadGuard {
+    type Target = MemoryPage;
+
+    fn deref(&self) -> &MemoryPage {
+        &self.page
+    }
+}
+
+pub struct MemoryWriteGuard<'a> {
+    relation: &'a MemoryRelation,
+    id: u32,
+    page: MemoryPage,
+}
+
+impl PageGuard for MemoryWriteGuard<'_> {
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Deref for MemoryWriteGuard<'_> {
+    type Target = MemoryPage;
+
+    fn deref(&self) -> &MemoryPage {
+        &self.page
+    }
+}
+
+impl DerefMut for MemoryWriteGuard<'_> {
+    fn deref_mut(&mut self) -> &mut MemoryPage {
+        &mut self.page
+    }
+}
+
+impl Drop for MemoryWriteGuard<'_> {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            let opaque = self.page.opaque;
+            let page = std::mem::replace(&mut self.page, MemoryPage::new(opaque));
+            self.relation.pages.borrow_mut()[self.id as usize] = page;
+        }
+    }
+}
+
+impl Relation for MemoryRelation {
+    type Page = MemoryPage;
+}
+
+impl RelationReadTypes for MemoryRelation {
+    type ReadGuard<'a> = MemoryReadGuard;
+}
+
+impl RelationRead for MemoryRelation {
+    fn read(&self, id: u32) -> MemoryReadGuard {
+        let page = self
+            .pages
+            .borrow()
+            .get(id as usize)
+            .expect("no such page")
+            .clone();
+        MemoryReadGuard { id, page }
+    }
+}
+
+impl RelationWriteTypes for MemoryRelation {
+    type WriteGuard<'a> = MemoryWriteGuard<'a>;
+}
+
+impl RelationWrite for MemoryRelation {
+    fn write(&self, id: u32) -> MemoryWriteGuard<'_> {
+        let page = self
+            .pages
+            .borrow()
+            .get(id as usize)
+            .expect("no such page")
+            .clone();
+        MemoryWriteGuard {
+            relation: self,
+            id,
+            page,
+        }
+    }
+    fn try_write(&self, id: u32) -> Option<MemoryWriteGuard<'_>> {
+        Some(self.write(id))
+    }
+    fn alloc(&self, opaque: Opaque) -> MemoryWriteGuard<'_> {
+        let mut pages = self.pages.borrow_mut();
+        let id = if let Some(id) = self.free.borrow_mut().pop() {
+            id
+        } else {
+            pages.push(MemoryPage::new(opaque));
+            (pages.len() - 1) as u32
+        };
+        MemoryWriteGuard {
+            relation: self,
+            id,
+            page: MemoryPage::new(opaque),
+        }
+    }
+    fn free(&self, mut guard: MemoryWriteGuard<'_>) {
+        guard.get_opaque_mut().set_deleted();
+        self.free.borrow_mut().push(guard.id);
+    }
+    fn vacuum(&self) {}
+}
+
+impl RelationLength for MemoryRelation {
+    fn len(&self) -> u32 {
+        self.pages.borrow().len() as u32
+    }
+}
+
+/// A directory for temporary files, which is removed on drop.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> Self {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let name = format!(
+            "bm25-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir(&path).expect("failed to create the temporary directory");
+        Self { path }
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Creates an empty file in the directory.
+    pub fn file(&self, name: &str) -> PathBuf {
+        let path = self.path.join(name);
+        std::fs::File::create_new(&path).expect("failed to create the temporary file");
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Builds an index of the documents as `CREATE INDEX` does with a single
+/// participant, where the payload of each document is its offset.
+pub fn build(options: Bm25IndexOptions, documents: &[Document]) -> MemoryRelation {
+    let index = MemoryRelation::new();
+    let dir = TempDir::new();
+    let mut records_writer = crate::io::records_writer(dir.path(), 0);
+    let mut mappings_writer = crate::io::mappings_writer(dir.path(), 0);
+    let mut positions_writer = options
+        .positions
+        .then(|| crate::io::positions_writer(dir.path(), 0));
+    let mut terms_writer = options
+        .dictionary
+        .then(|| crate::io::terms_writer(dir.path(), 0));
+    for (i, document) in documents.iter().enumerate() {
+        crate::io::write(
+            &mut records_writer,
+            &mut mappings_writer,
+            positions_writer.as_mut(),
+            terms_writer.as_mut(),
+            document,
+            payload(i),
+        );
+    }
+    records_writer.flush();
+    mappings_writer.flush();
+    if let Some(positions_writer) = positions_writer.as_mut() {
+        positions_writer.flush();
+    }
+    if let Some(terms_writer) = terms_writer.as_mut() {
+        terms_writer.flush();
+    }
+    drop((
+        records_writer,
+        mappings_writer,
+        positions_writer,
+        terms_writer,
+    ));
+    crate::io::locally_merge(dir.path(), 0);
+    let segment = crate::io::readers(dir.path(), 1, options.positions);
+    let dictionary = if options.dictionary {
+        crate::io::terms(dir.path(), 1)
+    } else {
+        Dictionary::new()
+    };
+    crate::build::build(options, &index, SEED, segment, dictionary);
+    index
+}
+
+/// Runs `VACUUM` on the index.
+pub fn maintain(index: &MemoryRelation) -> Result<(), Collision> {
+    let dir = TempDir::new();
+    let file = dir.file("relabel");
+    let work = dir.path().join("work");
+    std::fs::create_dir(&work).expect("failed to create the temporary directory");
+    crate::maintain::maintain(index, || (), &work, &file)
+}
+
+/// Seals documents that are not sealed yet as insertion does, returning
+/// whether the threshold is reached.
+pub fn seal(index: &MemoryRelation, threshold: u32) -> Result<bool, Collision> {
+    let dir = TempDir::new();
+    let file = dir.file("relabel");
+    let work = dir.path().join("work");
+    std::fs::create_dir(&work).expect("failed to create the temporary directory");
+    crate::maintain::seal(index, threshold, || (), &work, &file)
+}
+
+pub fn payload(i: usize) -> [u16; 3] {
+    [0, 0, i as u16 + 1]
+}
+
+/// Makes a document of terms with their frequencies, carrying their strings.
+pub fn document(terms: &[(&str, u32)]) -> Document {
+    let mut internal = terms
+        .iter()
+        .map(|&(term, value)| {
+            let key = intern(&SEED, term.as_bytes());
+            (Element { key, value }, term.as_bytes().to_vec())
+        })
+        .collect::<Vec<_>>();
+    internal.sort_unstable_by_key(|(element, _)| element.key);
+    let (internal, terms) = internal.into_iter().unzip();
+    Document::new(internal).with_terms(terms)
+}
+
+pub fn segments(index: &MemoryRelation) -> Vec<SegmentTuple> {
+    let meta_guard = index.read(0);
+    let meta_tuple = MetaTuple::deserialize_ref(meta_guard.get(1).expect("data corruption"));
+    let jump_guard = index.read(meta_tuple.ptr_jump());
+    let jump_tuple = JumpTuple::deserialize_ref(jump_guard.get(1).expect("data corruption"));
+    crate::segments::read(index, jump_tuple.ptr_segments())
+}
+
+/// Returns the payload and the fieldnorm of each live document of a segment.
+pub fn documents(index: &MemoryRelation, segment: &SegmentTuple) -> Vec<([u16; 3], u8)> {
+    let mut tape = TapeReader::new(segment.ptr_documents, |bytes| {
+        let document_tuple = DocumentTuple::deserialize_ref(bytes);
+        (
+            bool::from(document_tuple.deleted()),
+            document_tuple.payload(),
+            document_tuple.fieldnorm(),
+        )
+    });
+    let mut result = Vec::new();
+    while let Some((deleted, payload, fieldnorm)) = tape.next(index) {
+        if !deleted {
+            result.push((payload, fieldnorm));
+        }
+    }
+    result
+}
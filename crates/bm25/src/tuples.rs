@@ -13,6 +13,7 @@
 // Copyright (c) 2025-2026 TensorChord Inc.
 
 use crate::WIDTH;
+use crate::bm25::Scoring;
 use crate::vector::Element;
 use index::tuples::{Bool, MutChecker, Padding, RefChecker};
 use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
@@ -20,7 +21,7 @@ use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
 pub const ALIGN: usize = 8;
 pub type Tag = u64;
 const MAGIC: Tag = Tag::from_ne_bytes(*b"vchordbm");
-const VERSION: u64 = 1;
+const VERSION: u64 = 9;
 
 #[inline(always)]
 fn tag(source: &[u8]) -> Tag {
@@ -54,6 +55,12 @@ struct MetaTupleHeader {
     ptr_lock: u32,
     ptr_jump: u32,
     seed: [u8; 32],
+    weights: [f64; 4],
+    positions: Bool,
+    scoring: u8,
+    dictionary: Bool,
+    _padding_0: [Padding; 5],
+    parameter: f64,
 }
 
 pub struct MetaTuple {
@@ -62,6 +69,10 @@ pub struct MetaTuple {
     pub ptr_lock: u32,
     pub ptr_jump: u32,
     pub seed: [u8; 32],
+    pub weights: [f64; 4],
+    pub positions: bool,
+    pub scoring: Scoring,
+    pub dictionary: bool,
 }
 
 impl Tuple for MetaTuple {
@@ -75,6 +86,10 @@ impl Tuple for MetaTuple {
                 ptr_lock,
                 ptr_jump,
                 seed,
+                weights,
+                positions,
+                scoring,
+                dictionary,
             } => {
                 buffer.extend((MAGIC as Tag).to_ne_bytes());
                 buffer.extend(
@@ -85,6 +100,24 @@ impl Tuple for MetaTuple {
                         ptr_jump: *ptr_jump,
                         ptr_lock: *ptr_lock,
                         seed: *seed,
+                        weights: *weights,
+                        positions: (*positions).into(),
+                        scoring: match scoring {
+                            Scoring::Bm25 => 0,
+                            Scoring::Bm25Plus { .. } => 1,
+                            Scoring::Bm25L { .. } => 2,
+                            Scoring::TfIdf => 3,
+                            Scoring::LmDirichlet { .. } => 4,
+                            Scoring::LmJelinekMercer { .. } => 5,
+                        },
+                        dictionary: (*dictionary).into(),
+                        _padding_0: Default::default(),
+                        parameter: match *scoring {
+                            Scoring::Bm25Plus { delta } | Scoring::Bm25L { delta } => delta,
+                            Scoring::LmDirichlet { mu } => mu,
+                            Scoring::LmJelinekMercer { lambda } => lambda,
+                            Scoring::Bm25 | Scoring::TfIdf => 0.0,
+                        },
                     }
                     .as_bytes(),
                 );
@@ -136,13 +169,181 @@ impl<'a> MetaTupleReader<'a> {
     pub fn seed(self) -> [u8; 32] {
         self.header.seed
     }
+    pub fn weights(self) -> [f64; 4] {
+        self.header.weights
+    }
+    pub fn unit(self) -> f64 {
+        crate::bm25::unit(&self.header.weights)
+    }
+    pub fn positions(self) -> bool {
+        self.header.positions.into()
+    }
+    pub fn dictionary(self) -> bool {
+        self.header.dictionary.into()
+    }
+    pub fn scoring(self) -> Scoring {
+        let parameter = self.header.parameter;
+        match self.header.scoring {
+            0 => Scoring::Bm25,
+            1 => Scoring::Bm25Plus { delta: parameter },
+            2 => Scoring::Bm25L { delta: parameter },
+            3 => Scoring::TfIdf,
+            4 => Scoring::LmDirichlet { mu: parameter },
+            5 => Scoring::LmJelinekMercer { lambda: parameter },
+            _ => panic!("deserialization: bad scoring function"),
+        }
+    }
 }
 
 #[repr(C, align(8))]
 #[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
 struct JumpTupleHeader {
     ptr_vectors: u32,
+    ptr_segments: u32,
+    ptr_terms: u32,
+    depth_terms: u32,
+    start_terms: u32,
+    free_terms: u32,
+    ptr_strings: u32,
+    depth_strings: u32,
+    start_strings: u32,
+    free_strings: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct JumpTuple {
+    pub ptr_vectors: u32,
+    pub ptr_segments: u32,
+    pub ptr_terms: u32,
+    pub depth_terms: u32,
+    pub start_terms: u32,
+    pub free_terms: u32,
+    pub ptr_strings: u32,
+    pub depth_strings: u32,
+    pub start_strings: u32,
+    pub free_strings: u32,
+}
+
+impl Tuple for JumpTuple {
+    fn serialize(&self) -> Vec<u8> {
+        JumpTupleHeader {
+            ptr_vectors: self.ptr_vectors,
+            ptr_segments: self.ptr_segments,
+            ptr_terms: self.ptr_terms,
+            depth_terms: self.depth_terms,
+            start_terms: self.start_terms,
+            free_terms: self.free_terms,
+            ptr_strings: self.ptr_strings,
+            depth_strings: self.depth_strings,
+            start_strings: self.start_strings,
+            free_strings: self.free_strings,
+        }
+        .as_bytes()
+        .to_vec()
+    }
+}
+
+impl WithReader for JumpTuple {
+    type Reader<'a> = JumpTupleReader<'a>;
+
+    fn deserialize_ref(source: &[u8]) -> Self::Reader<'_> {
+        let checker = RefChecker::new(source);
+        let header: &JumpTupleHeader = checker.prefix(0_u16);
+        JumpTupleReader { header }
+    }
+}
+
+impl WithWriter for JumpTuple {
+    type Writer<'a> = JumpTupleWriter<'a>;
+
+    fn deserialize_mut(source: &mut [u8]) -> Self::Writer<'_> {
+        let mut checker = MutChecker::new(source);
+        let header: &mut JumpTupleHeader = checker.prefix(0_u16);
+        JumpTupleWriter { header }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct JumpTupleReader<'a> {
+    header: &'a JumpTupleHeader,
+}
+
+impl<'a> JumpTupleReader<'a> {
+    pub fn ptr_vectors(self) -> u32 {
+        self.header.ptr_vectors
+    }
+    pub fn ptr_segments(self) -> u32 {
+        self.header.ptr_segments
+    }
+    pub fn ptr_terms(self) -> u32 {
+        self.header.ptr_terms
+    }
+    pub fn depth_terms(self) -> u32 {
+        self.header.depth_terms
+    }
+    pub fn start_terms(self) -> u32 {
+        self.header.start_terms
+    }
+    pub fn free_terms(self) -> u32 {
+        self.header.free_terms
+    }
+    pub fn ptr_strings(self) -> u32 {
+        self.header.ptr_strings
+    }
+    pub fn depth_strings(self) -> u32 {
+        self.header.depth_strings
+    }
+    pub fn start_strings(self) -> u32 {
+        self.header.start_strings
+    }
+    pub fn free_strings(self) -> u32 {
+        self.header.free_strings
+    }
+}
+
+#[derive(Debug)]
+pub struct JumpTupleWriter<'a> {
+    header: &'a mut JumpTupleHeader,
+}
+
+impl<'a> JumpTupleWriter<'a> {
+    pub fn ptr_vectors(&mut self) -> &mut u32 {
+        &mut self.header.ptr_vectors
+    }
+    pub fn ptr_segments(&mut self) -> &mut u32 {
+        &mut self.header.ptr_segments
+    }
+    pub fn ptr_terms(&mut self) -> &mut u32 {
+        &mut self.header.ptr_terms
+    }
+    pub fn depth_terms(&mut self) -> &mut u32 {
+        &mut self.header.depth_terms
+    }
+    pub fn start_terms(&mut self) -> &mut u32 {
+        &mut self.header.start_terms
+    }
+    pub fn free_terms(&mut self) -> &mut u32 {
+        &mut self.header.free_terms
+    }
+    pub fn ptr_strings(&mut self) -> &mut u32 {
+        &mut self.header.ptr_strings
+    }
+    pub fn depth_strings(&mut self) -> &mut u32 {
+        &mut self.header.depth_strings
+    }
+    pub fn start_strings(&mut self) -> &mut u32 {
+        &mut self.header.start_strings
+    }
+    pub fn free_strings(&mut self) -> &mut u32 {
+        &mut self.header.free_strings
+    }
+}
+
+#[repr(C, align(8))]
+#[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
+struct SegmentTupleHeader {
     number_of_documents: u32,
+    number_of_deleted_documents: u32,
     sum_of_document_lengths: u64,
     width_1_documents: u16,
     width_0_documents: u16,
@@ -156,13 +357,13 @@ struct JumpTupleHeader {
     ptr_tokens: u32,
     ptr_summaries: u32,
     ptr_blocks: u32,
-    _padding_0: [Padding; 4],
+    ptr_positions: u32,
 }
 
 #[derive(Debug, Clone)]
-pub struct JumpTuple {
-    pub ptr_vectors: u32,
+pub struct SegmentTuple {
     pub number_of_documents: u32,
+    pub number_of_deleted_documents: u32,
     pub sum_of_document_lengths: u64,
     pub width_1_documents: u16,
     pub width_0_documents: u16,
@@ -176,13 +377,14 @@ pub struct JumpTuple {
     pub ptr_tokens: u32,
     pub ptr_summaries: u32,
     pub ptr_blocks: u32,
+    pub ptr_positions: u32,
 }
 
-impl Tuple for JumpTuple {
+impl Tuple for SegmentTuple {
     fn serialize(&self) -> Vec<u8> {
-        JumpTupleHeader {
-            ptr_vectors: self.ptr_vectors,
+        SegmentTupleHeader {
             number_of_documents: self.number_of_documents,
+            number_of_deleted_documents: self.number_of_deleted_documents,
             sum_of_document_lengths: self.sum_of_document_lengths,
             width_1_documents: self.width_1_documents,
             width_0_documents: self.width_0_documents,
@@ -196,45 +398,45 @@ impl Tuple for JumpTuple {
             ptr_tokens: self.ptr_tokens,
             ptr_summaries: self.ptr_summaries,
             ptr_blocks: self.ptr_blocks,
-            _padding_0: Default::default(),
+            ptr_positions: self.ptr_positions,
         }
         .as_bytes()
         .to_vec()
     }
 }
 
-impl WithReader for JumpTuple {
-    type Reader<'a> = JumpTupleReader<'a>;
+impl WithReader for SegmentTuple {
+    type Reader<'a> = SegmentTupleReader<'a>;
 
     fn deserialize_ref(source: &[u8]) -> Self::Reader<'_> {
         let checker = RefChecker::new(source);
-        let header: &JumpTupleHeader = checker.prefix(0_u16);
-        JumpTupleReader { header }
+        let header: &SegmentTupleHeader = checker.prefix(0_u16);
+        SegmentTupleReader { header }
     }
 }
 
-impl WithWriter for JumpTuple {
-    type Writer<'a> = JumpTupleWriter<'a>;
+impl WithWriter for SegmentTuple {
+    type Writer<'a> = SegmentTupleWriter<'a>;
 
     fn deserialize_mut(source: &mut [u8]) -> Self::Writer<'_> {
         let mut checker = MutChecker::new(source);
-        let header: &mut JumpTupleHeader = checker.prefix(0_u16);
-        JumpTupleWriter { header }
+        let header: &mut SegmentTupleHeader = checker.prefix(0_u16);
+        SegmentTupleWriter { header }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct JumpTupleReader<'a> {
-    header: &'a JumpTupleHeader,
+pub struct SegmentTupleReader<'a> {
+    header: &'a SegmentTupleHeader,
 }
 
-impl<'a> JumpTupleReader<'a> {
-    pub fn ptr_vectors(self) -> u32 {
-        self.header.ptr_vectors
-    }
+impl<'a> SegmentTupleReader<'a> {
     pub fn number_of_documents(self) -> u32 {
         self.header.number_of_documents
     }
+    pub fn number_of_deleted_documents(self) -> u32 {
+        self.header.number_of_deleted_documents
+    }
     pub fn sum_of_document_lengths(self) -> u64 {
         self.header.sum_of_document_lengths
     }
@@ -250,12 +452,18 @@ impl<'a> JumpTupleReader<'a> {
     pub fn start_documents(self) -> u32 {
         self.header.start_documents
     }
+    pub fn free_documents(self) -> u32 {
+        self.header.free_documents
+    }
     pub fn depth_tokens(self) -> u32 {
         self.header.depth_tokens
     }
     pub fn start_tokens(self) -> u32 {
         self.header.start_tokens
     }
+    pub fn free_tokens(self) -> u32 {
+        self.header.free_tokens
+    }
     pub fn ptr_documents(self) -> u32 {
         self.header.ptr_documents
     }
@@ -268,58 +476,19 @@ impl<'a> JumpTupleReader<'a> {
     pub fn ptr_blocks(self) -> u32 {
         self.header.ptr_blocks
     }
+    pub fn ptr_positions(self) -> u32 {
+        self.header.ptr_positions
+    }
 }
 
 #[derive(Debug)]
-pub struct JumpTupleWriter<'a> {
-    header: &'a mut JumpTupleHeader,
+pub struct SegmentTupleWriter<'a> {
+    header: &'a mut SegmentTupleHeader,
 }
 
-impl<'a> JumpTupleWriter<'a> {
-    pub fn ptr_vectors(&mut self) -> &mut u32 {
-        &mut self.header.ptr_vectors
-    }
-    pub fn number_of_documents(&mut self) -> &mut u32 {
-        &mut self.header.number_of_documents
-    }
-    pub fn sum_of_document_lengths(&mut self) -> &mut u64 {
-        &mut self.header.sum_of_document_lengths
-    }
-    pub fn width_1_documents(&mut self) -> &mut u16 {
-        &mut self.header.width_1_documents
-    }
-    pub fn width_0_documents(&mut self) -> &mut u16 {
-        &mut self.header.width_0_documents
-    }
-    pub fn depth_documents(&mut self) -> &mut u32 {
-        &mut self.header.depth_documents
-    }
-    pub fn start_documents(&mut self) -> &mut u32 {
-        &mut self.header.start_documents
-    }
-    pub fn free_documents(&mut self) -> &mut u32 {
-        &mut self.header.free_documents
-    }
-    pub fn depth_tokens(&mut self) -> &mut u32 {
-        &mut self.header.depth_tokens
-    }
-    pub fn start_tokens(&mut self) -> &mut u32 {
-        &mut self.header.start_tokens
-    }
-    pub fn free_tokens(&mut self) -> &mut u32 {
-        &mut self.header.free_tokens
-    }
-    pub fn ptr_documents(&mut self) -> &mut u32 {
-        &mut self.header.ptr_documents
-    }
-    pub fn ptr_tokens(&mut self) -> &mut u32 {
-        &mut self.header.ptr_tokens
-    }
-    pub fn ptr_summaries(&mut self) -> &mut u32 {
-        &mut self.header.ptr_summaries
-    }
-    pub fn ptr_blocks(&mut self) -> &mut u32 {
-        &mut self.header.ptr_blocks
+impl<'a> SegmentTupleWriter<'a> {
+    pub fn number_of_deleted_documents(&mut self) -> &mut u32 {
+        &mut self.header.number_of_deleted_documents
     }
 }
 
@@ -349,6 +518,22 @@ struct VectorTupleHeader2 {
     _padding_0: [Padding; 7],
 }
 
+#[repr(C, align(8))]
+#[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
+struct VectorTupleHeader3 {
+    positions_s: u16,
+    positions_e: u16,
+    _padding_0: [Padding; 4],
+}
+
+#[repr(C, align(8))]
+#[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
+struct VectorTupleHeader4 {
+    terms_s: u16,
+    terms_e: u16,
+    _padding_0: [Padding; 4],
+}
+
 pub enum VectorTuple {
     _0 {
         deleted: Bool,
@@ -361,6 +546,12 @@ pub enum VectorTuple {
     _2 {
         fieldnorm: u8,
     },
+    _3 {
+        positions: Vec<u8>,
+    },
+    _4 {
+        terms: Vec<u8>,
+    },
 }
 
 impl Tuple for VectorTuple {
@@ -424,6 +615,46 @@ impl Tuple for VectorTuple {
                     .as_bytes(),
                 );
             }
+            VectorTuple::_3 { positions } => {
+                buffer.extend((3 as Tag).to_ne_bytes());
+                buffer.extend(std::iter::repeat_n(0, size_of::<VectorTupleHeader3>()));
+                // positions
+                let positions_s = buffer.len() as u16;
+                buffer.extend(positions.as_bytes());
+                let positions_e = buffer.len() as u16;
+                while buffer.len() % ALIGN != 0 {
+                    buffer.push(0);
+                }
+                // header
+                buffer[size_of::<Tag>()..][..size_of::<VectorTupleHeader3>()].copy_from_slice(
+                    VectorTupleHeader3 {
+                        positions_s,
+                        positions_e,
+                        _padding_0: Default::default(),
+                    }
+                    .as_bytes(),
+                );
+            }
+            VectorTuple::_4 { terms } => {
+                buffer.extend((4 as Tag).to_ne_bytes());
+                buffer.extend(std::iter::repeat_n(0, size_of::<VectorTupleHeader4>()));
+                // terms
+                let terms_s = buffer.len() as u16;
+                buffer.extend(terms.as_bytes());
+                let terms_e = buffer.len() as u16;
+                while buffer.len() % ALIGN != 0 {
+                    buffer.push(0);
+                }
+                // header
+                buffer[size_of::<Tag>()..][..size_of::<VectorTupleHeader4>()].copy_from_slice(
+                    VectorTupleHeader4 {
+                        terms_s,
+                        terms_e,
+                        _padding_0: Default::default(),
+                    }
+                    .as_bytes(),
+                );
+            }
         }
         buffer
     }
@@ -450,6 +681,32 @@ impl VectorTuple {
             None
         }
     }
+    pub fn fit_3(freespace: u16) -> Option<usize> {
+        let mut freespace = freespace as isize;
+        freespace &= !(ALIGN - 1) as isize;
+        freespace -= size_of::<Tag>() as isize;
+        freespace &= !(ALIGN - 1) as isize;
+        freespace -= size_of::<VectorTupleHeader3>() as isize;
+        freespace &= !(ALIGN - 1) as isize;
+        if freespace > 0 {
+            Some(freespace as usize)
+        } else {
+            None
+        }
+    }
+    pub fn fit_4(freespace: u16) -> Option<usize> {
+        let mut freespace = freespace as isize;
+        freespace &= !(ALIGN - 1) as isize;
+        freespace -= size_of::<Tag>() as isize;
+        freespace &= !(ALIGN - 1) as isize;
+        freespace -= size_of::<VectorTupleHeader4>() as isize;
+        freespace &= !(ALIGN - 1) as isize;
+        if freespace > 0 {
+            Some(freespace as usize)
+        } else {
+            None
+        }
+    }
 }
 
 impl WithReader for VectorTuple {
@@ -475,6 +732,18 @@ impl WithReader for VectorTuple {
                 let header: &VectorTupleHeader2 = checker.prefix(size_of::<Tag>());
                 VectorTupleReader::_2(VectorTupleReader2 { header })
             }
+            3 => {
+                let checker = RefChecker::new(source);
+                let header: &VectorTupleHeader3 = checker.prefix(size_of::<Tag>());
+                let positions = checker.bytes(header.positions_s, header.positions_e);
+                VectorTupleReader::_3(VectorTupleReader3 { header, positions })
+            }
+            4 => {
+                let checker = RefChecker::new(source);
+                let header: &VectorTupleHeader4 = checker.prefix(size_of::<Tag>());
+                let terms = checker.bytes(header.terms_s, header.terms_e);
+                VectorTupleReader::_4(VectorTupleReader4 { header, terms })
+            }
             _ => panic!("deserialization: bad magic number"),
         }
     }
@@ -502,6 +771,16 @@ impl WithWriter for VectorTuple {
                 let header: &mut VectorTupleHeader2 = checker.prefix(size_of::<Tag>());
                 VectorTupleWriter::_2(VectorTupleWriter2 { header })
             }
+            3 => {
+                let mut checker = MutChecker::new(source);
+                let header: &mut VectorTupleHeader3 = checker.prefix(size_of::<Tag>());
+                VectorTupleWriter::_3(VectorTupleWriter3 { header })
+            }
+            4 => {
+                let mut checker = MutChecker::new(source);
+                let header: &mut VectorTupleHeader4 = checker.prefix(size_of::<Tag>());
+                VectorTupleWriter::_4(VectorTupleWriter4 { header })
+            }
             _ => panic!("deserialization: bad magic number"),
         }
     }
@@ -513,6 +792,8 @@ pub enum VectorTupleReader<'a> {
     _1(VectorTupleReader1<'a>),
     #[allow(dead_code)]
     _2(VectorTupleReader2<'a>),
+    _3(VectorTupleReader3<'a>),
+    _4(VectorTupleReader4<'a>),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -557,12 +838,42 @@ impl VectorTupleReader2<'_> {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct VectorTupleReader3<'a> {
+    #[allow(dead_code)]
+    header: &'a VectorTupleHeader3,
+    positions: &'a [u8],
+}
+
+impl<'a> VectorTupleReader3<'a> {
+    pub fn positions(self) -> &'a [u8] {
+        self.positions
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct VectorTupleReader4<'a> {
+    #[allow(dead_code)]
+    header: &'a VectorTupleHeader4,
+    terms: &'a [u8],
+}
+
+impl<'a> VectorTupleReader4<'a> {
+    pub fn terms(self) -> &'a [u8] {
+        self.terms
+    }
+}
+
 pub enum VectorTupleWriter<'a> {
     _0(VectorTupleWriter0<'a>),
     #[allow(dead_code)]
     _1(VectorTupleWriter1<'a>),
     #[allow(dead_code)]
     _2(VectorTupleWriter2<'a>),
+    #[allow(dead_code)]
+    _3(VectorTupleWriter3<'a>),
+    #[allow(dead_code)]
+    _4(VectorTupleWriter4<'a>),
 }
 
 #[derive(Debug)]
@@ -599,6 +910,18 @@ pub struct VectorTupleWriter2<'a> {
     header: &'a mut VectorTupleHeader2,
 }
 
+#[derive(Debug)]
+pub struct VectorTupleWriter3<'a> {
+    #[allow(dead_code)]
+    header: &'a mut VectorTupleHeader3,
+}
+
+#[derive(Debug)]
+pub struct VectorTupleWriter4<'a> {
+    #[allow(dead_code)]
+    header: &'a mut VectorTupleHeader4,
+}
+
 #[repr(C, align(8))]
 #[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
 struct AddressDocumentsTupleHeader {
@@ -839,11 +1162,13 @@ struct TokenTupleHeader {
     wptr_summaries: Pointer,
     number_of_documents: u32,
     wand_term_frequency: u32,
+    sum_of_term_frequencies: u64,
 }
 
 pub struct TokenTuple {
     pub id: [u8; WIDTH],
     pub number_of_documents: u32,
+    pub sum_of_term_frequencies: u64,
     pub wand_fieldnorm: u8,
     pub wand_term_frequency: u32,
     pub wptr_summaries: (u32, u16),
@@ -854,6 +1179,7 @@ impl Tuple for TokenTuple {
         TokenTupleHeader {
             id: self.id,
             number_of_documents: self.number_of_documents,
+            sum_of_term_frequencies: self.sum_of_term_frequencies,
             wand_fieldnorm: self.wand_fieldnorm,
             wand_term_frequency: self.wand_term_frequency,
             wptr_summaries: Pointer::new(self.wptr_summaries),
@@ -886,6 +1212,9 @@ impl<'a> TokenTupleReader<'a> {
     pub fn number_of_documents(self) -> u32 {
         self.header.number_of_documents
     }
+    pub fn sum_of_term_frequencies(self) -> u64 {
+        self.header.sum_of_term_frequencies
+    }
     pub fn wand_fieldnorm(self) -> u8 {
         self.header.wand_fieldnorm
     }
@@ -979,7 +1308,7 @@ struct BlockTupleHeader {
     compressed_document_ids_e: u16,
     compressed_term_frequencies_s: u16,
     compressed_term_frequencies_e: u16,
-    _padding_0: [Padding; 6],
+    wptr_positions: Pointer,
 }
 
 pub struct BlockTuple {
@@ -987,6 +1316,7 @@ pub struct BlockTuple {
     pub compressed_document_ids: Vec<u8>,
     pub metadata_term_frequencies: u8,
     pub compressed_term_frequencies: Vec<u8>,
+    pub wptr_positions: Pointer,
 }
 
 impl Tuple for BlockTuple {
@@ -1016,7 +1346,7 @@ impl Tuple for BlockTuple {
                 compressed_document_ids_e,
                 compressed_term_frequencies_s,
                 compressed_term_frequencies_e,
-                _padding_0: Default::default(),
+                wptr_positions: self.wptr_positions,
             }
             .as_bytes(),
         );
@@ -1066,6 +1396,247 @@ impl<'a> BlockTupleReader<'a> {
     pub fn compressed_term_frequencies(self) -> &'a [u8] {
         self.compressed_term_frequencies
     }
+    pub fn wptr_positions(self) -> Pointer {
+        self.header.wptr_positions
+    }
+}
+
+#[repr(C, align(8))]
+#[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
+struct PositionsTupleHeader {
+    bytes_s: u16,
+    bytes_e: u16,
+    _padding_0: [Padding; 4],
+}
+
+/// A chunk of the positions of a block, where `_1` is followed by more chunks
+/// and `_0` is the last one.
+pub enum PositionsTuple {
+    _0 { bytes: Vec<u8> },
+    _1 { bytes: Vec<u8> },
+}
+
+impl Tuple for PositionsTuple {
+    fn serialize(&self) -> Vec<u8> {
+        let mut buffer = Vec::<u8>::new();
+        let (tag, bytes) = match self {
+            PositionsTuple::_0 { bytes } => (0 as Tag, bytes),
+            PositionsTuple::_1 { bytes } => (1 as Tag, bytes),
+        };
+        buffer.extend(tag.to_ne_bytes());
+        buffer.extend(std::iter::repeat_n(0, size_of::<PositionsTupleHeader>()));
+        // bytes
+        let bytes_s = buffer.len() as u16;
+        buffer.extend(bytes.as_bytes());
+        let bytes_e = buffer.len() as u16;
+        while buffer.len() % ALIGN != 0 {
+            buffer.push(0);
+        }
+        // header
+        buffer[size_of::<Tag>()..][..size_of::<PositionsTupleHeader>()].copy_from_slice(
+            PositionsTupleHeader {
+                bytes_s,
+                bytes_e,
+                _padding_0: Default::default(),
+            }
+            .as_bytes(),
+        );
+        buffer
+    }
+}
+
+impl PositionsTuple {
+    pub fn estimate_size_0(bytes: usize) -> usize {
+        let mut size = 0_usize;
+        size += size_of::<Tag>();
+        size += size_of::<PositionsTupleHeader>();
+        size += bytes.next_multiple_of(ALIGN);
+        size
+    }
+    pub fn fit_1(freespace: u16) -> Option<usize> {
+        let mut freespace = freespace as isize;
+        freespace &= !(ALIGN - 1) as isize;
+        freespace -= size_of::<Tag>() as isize;
+        freespace &= !(ALIGN - 1) as isize;
+        freespace -= size_of::<PositionsTupleHeader>() as isize;
+        freespace &= !(ALIGN - 1) as isize;
+        if freespace > 0 {
+            Some(freespace as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl WithReader for PositionsTuple {
+    type Reader<'a> = PositionsTupleReader<'a>;
+
+    fn deserialize_ref(source: &[u8]) -> Self::Reader<'_> {
+        let tag = tag(source);
+        let last = match tag {
+            0 => true,
+            1 => false,
+            _ => panic!("deserialization: bad magic number"),
+        };
+        let checker = RefChecker::new(source);
+        let header: &PositionsTupleHeader = checker.prefix(size_of::<Tag>());
+        let bytes = checker.bytes(header.bytes_s, header.bytes_e);
+        PositionsTupleReader { last, bytes }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PositionsTupleReader<'a> {
+    last: bool,
+    bytes: &'a [u8],
+}
+
+impl<'a> PositionsTupleReader<'a> {
+    pub fn last(self) -> bool {
+        self.last
+    }
+    pub fn bytes(self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+#[repr(C, align(8))]
+#[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
+struct TermTupleHeader {
+    key: [u8; WIDTH],
+    term_s: u16,
+    term_e: u16,
+    _padding_0: [Padding; 4],
+}
+
+/// The original string of a hashed key.
+pub struct TermTuple {
+    pub key: [u8; WIDTH],
+    pub term: Vec<u8>,
+}
+
+impl Tuple for TermTuple {
+    fn serialize(&self) -> Vec<u8> {
+        let mut buffer = Vec::<u8>::new();
+        buffer.extend(std::iter::repeat_n(0, size_of::<TermTupleHeader>()));
+        // term
+        let term_s = buffer.len() as u16;
+        buffer.extend(self.term.as_bytes());
+        let term_e = buffer.len() as u16;
+        while buffer.len() % ALIGN != 0 {
+            buffer.push(0);
+        }
+        // header
+        buffer[..size_of::<TermTupleHeader>()].copy_from_slice(
+            TermTupleHeader {
+                key: self.key,
+                term_s,
+                term_e,
+                _padding_0: Default::default(),
+            }
+            .as_bytes(),
+        );
+        buffer
+    }
+}
+
+impl WithReader for TermTuple {
+    type Reader<'a> = TermTupleReader<'a>;
+
+    fn deserialize_ref(source: &[u8]) -> Self::Reader<'_> {
+        let checker = RefChecker::new(source);
+        let header: &TermTupleHeader = checker.prefix(0_u16);
+        let term = checker.bytes(header.term_s, header.term_e);
+        TermTupleReader { header, term }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TermTupleReader<'a> {
+    header: &'a TermTupleHeader,
+    term: &'a [u8],
+}
+
+impl<'a> TermTupleReader<'a> {
+    pub fn key(self) -> [u8; WIDTH] {
+        self.header.key
+    }
+    pub fn term(self) -> &'a [u8] {
+        self.term
+    }
+}
+
+#[repr(C, align(8))]
+#[derive(Debug, Clone, FromBytes, IntoBytes, Immutable, KnownLayout)]
+struct StringTupleHeader {
+    prefix: [u8; WIDTH],
+    key: [u8; WIDTH],
+    term_s: u16,
+    term_e: u16,
+    _padding_0: [Padding; 4],
+}
+
+/// The original string of a hashed key, led by its first `WIDTH` bytes so
+/// that strings are found by their prefixes.
+pub struct StringTuple {
+    pub prefix: [u8; WIDTH],
+    pub key: [u8; WIDTH],
+    pub term: Vec<u8>,
+}
+
+impl Tuple for StringTuple {
+    fn serialize(&self) -> Vec<u8> {
+        let mut buffer = Vec::<u8>::new();
+        buffer.extend(std::iter::repeat_n(0, size_of::<StringTupleHeader>()));
+        // term
+        let term_s = buffer.len() as u16;
+        buffer.extend(self.term.as_bytes());
+        let term_e = buffer.len() as u16;
+        while buffer.len() % ALIGN != 0 {
+            buffer.push(0);
+        }
+        // header
+        buffer[..size_of::<StringTupleHeader>()].copy_from_slice(
+            StringTupleHeader {
+                prefix: self.prefix,
+                key: self.key,
+                term_s,
+                term_e,
+                _padding_0: Default::default(),
+            }
+            .as_bytes(),
+        );
+        buffer
+    }
+}
+
+impl WithReader for StringTuple {
+    type Reader<'a> = StringTupleReader<'a>;
+
+    fn deserialize_ref(source: &[u8]) -> Self::Reader<'_> {
+        let checker = RefChecker::new(source);
+        let header: &StringTupleHeader = checker.prefix(0_u16);
+        let term = checker.bytes(header.term_s, header.term_e);
+        StringTupleReader { header, term }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StringTupleReader<'a> {
+    header: &'a StringTupleHeader,
+    term: &'a [u8],
+}
+
+impl<'a> StringTupleReader<'a> {
+    pub fn prefix(self) -> [u8; WIDTH] {
+        self.header.prefix
+    }
+    pub fn key(self) -> [u8; WIDTH] {
+        self.header.key
+    }
+    pub fn term(self) -> &'a [u8] {
+        self.term
+    }
 }
 
 #[repr(C, packed(2))]
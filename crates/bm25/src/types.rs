@@ -12,18 +12,66 @@
 //
 // Copyright (c) 2025-2026 TensorChord Inc.
 
+use crate::bm25::Scoring;
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Bm25Scoring {
+    #[default]
+    #[serde(rename = "bm25")]
+    Bm25,
+    #[serde(rename = "bm25plus")]
+    Bm25Plus,
+    #[serde(rename = "bm25l")]
+    Bm25L,
+    #[serde(rename = "tfidf")]
+    TfIdf,
+    #[serde(rename = "lmdirichlet")]
+    LmDirichlet,
+    #[serde(rename = "lmjelinekmercer")]
+    LmJelinekMercer,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
 pub struct Bm25IndexOptions {
     #[serde(default = "Bm25IndexOptions::default_k1")]
-    #[validate(range(min = 1.2, max = 2.0))]
+    #[validate(range(min = 0.0, max = 3.0))]
     pub k1: f64,
     #[serde(default = "Bm25IndexOptions::default_b")]
     #[validate(range(min = 0.0, max = 1.0))]
     pub b: f64,
+    /// Weights of labels `D`, `C`, `B` and `A`. A document is scored as if
+    /// the positions under each label were repeated by its weight, so term
+    /// frequencies and the document length are both weighted, as in the simple
+    /// BM25F of Robertson et al. All labels share `b`.
+    #[serde(default = "Bm25IndexOptions::default_weights")]
+    #[validate(custom(function = "Bm25IndexOptions::validate_weights"))]
+    pub weights: [f64; 4],
+    /// Stores positions of terms, which phrase queries require.
+    #[serde(default)]
+    pub positions: bool,
+    /// Stores strings of terms longer than the width of keys, which are
+    /// otherwise only kept as hashes.
+    #[serde(default)]
+    pub dictionary: bool,
+    #[serde(default)]
+    pub scoring: Bm25Scoring,
+    /// The lower bound of term frequency normalization of `bm25plus` and
+    /// `bm25l`, which is 1.0 and 0.5 by default respectively.
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 2.0))]
+    pub delta: Option<f64>,
+    /// The Dirichlet prior of `lmdirichlet`, which is 2000 by default.
+    #[serde(default)]
+    #[validate(range(min = 1.0, max = 100000.0))]
+    pub mu: Option<f64>,
+    /// The weight of the collection model in `lmjelinekmercer`, which is 0.1
+    /// by default.
+    #[serde(default)]
+    #[validate(range(exclusive_min = 0.0, exclusive_max = 1.0))]
+    pub lambda: Option<f64>,
 }
 
 impl Bm25IndexOptions {
@@ -33,6 +81,48 @@ impl Bm25IndexOptions {
     fn default_b() -> f64 {
         0.75
     }
+    fn default_weights() -> [f64; 4] {
+        [1.0; 4]
+    }
+    fn validate_weights(weights: &[f64; 4]) -> Result<(), ValidationError> {
+        let valid = |weight: f64| {
+            (0.01..=100.0).contains(&weight)
+                && (weight * 100.0 - (weight * 100.0).round()).abs() < 1e-6
+        };
+        if weights.iter().all(|&weight| valid(weight)) {
+            Ok(())
+        } else {
+            Err(ValidationError::new(
+                "weights must be between 0.01 and 100 with at most two decimal places",
+            ))
+        }
+    }
+}
+
+impl Bm25IndexOptions {
+    /// Multipliers of positions under each label, in units of stored term
+    /// frequency.
+    pub fn multipliers(&self) -> [u32; 4] {
+        crate::bm25::multipliers(&self.weights)
+    }
+    pub(crate) fn scoring(&self) -> Scoring {
+        match self.scoring {
+            Bm25Scoring::Bm25 => Scoring::Bm25,
+            Bm25Scoring::Bm25Plus => Scoring::Bm25Plus {
+                delta: self.delta.unwrap_or(1.0),
+            },
+            Bm25Scoring::Bm25L => Scoring::Bm25L {
+                delta: self.delta.unwrap_or(0.5),
+            },
+            Bm25Scoring::TfIdf => Scoring::TfIdf,
+            Bm25Scoring::LmDirichlet => Scoring::LmDirichlet {
+                mu: self.mu.unwrap_or(2000.0),
+            },
+            Bm25Scoring::LmJelinekMercer => Scoring::LmJelinekMercer {
+                lambda: self.lambda.unwrap_or(0.1),
+            },
+        }
+    }
 }
 
 impl Default for Bm25IndexOptions {
@@ -40,6 +130,13 @@ impl Default for Bm25IndexOptions {
         Self {
             k1: Self::default_k1(),
             b: Self::default_b(),
+            weights: Self::default_weights(),
+            positions: false,
+            dictionary: false,
+            scoring: Bm25Scoring::default(),
+            delta: None,
+            mu: None,
+            lambda: None,
         }
     }
 }
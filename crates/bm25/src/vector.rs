@@ -34,6 +34,12 @@ pub fn intern(seed: &[u8; 32], string: &[u8]) -> [u8; WIDTH] {
     }
 }
 
+/// Returns whether the key is a hash of its string rather than the string
+/// itself, so that the string cannot be recovered from the key.
+pub fn is_hashed(key: &[u8; WIDTH]) -> bool {
+    key[WIDTH - 1] != 0
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, IntoBytes, FromBytes, Immutable, KnownLayout)]
 pub struct Element {
@@ -44,6 +50,8 @@ pub struct Element {
 #[derive(Debug, Clone)]
 pub struct Document {
     internal: Vec<Element>,
+    positions: Option<Vec<Vec<u32>>>,
+    terms: Option<Vec<Vec<u8>>>,
 }
 
 impl Document {
@@ -60,7 +68,40 @@ impl Document {
         if !internal.iter().all(|&Element { value, .. }| value != 0) {
             return None;
         }
-        Some(Self { internal })
+        Some(Self {
+            internal,
+            positions: None,
+            terms: None,
+        })
+    }
+
+    /// Attaches the sorted positions of each term.
+    pub fn with_positions(self, positions: Vec<Vec<u32>>) -> Self {
+        assert_eq!(self.internal.len(), positions.len(), "invalid data");
+        assert!(positions.iter().all(|x| x.is_sorted()), "invalid data");
+        Self {
+            positions: Some(positions),
+            ..self
+        }
+    }
+
+    #[inline(always)]
+    pub fn positions(&self) -> Option<&[Vec<u32>]> {
+        self.positions.as_deref()
+    }
+
+    /// Attaches the string of each term.
+    pub fn with_terms(self, terms: Vec<Vec<u8>>) -> Self {
+        assert_eq!(self.internal.len(), terms.len(), "invalid data");
+        Self {
+            terms: Some(terms),
+            ..self
+        }
+    }
+
+    #[inline(always)]
+    pub fn terms(&self) -> Option<&[Vec<u8>]> {
+        self.terms.as_deref()
     }
 
     #[inline(always)]
@@ -96,6 +137,21 @@ impl Document {
 #[derive(Debug, Clone)]
 pub struct Query {
     internal: Vec<[u8; WIDTH]>,
+    weights: Vec<f64>,
+    must: Vec<[u8; WIDTH]>,
+    must_not: Vec<[u8; WIDTH]>,
+    minimum_should_match: usize,
+    phrases: Vec<Phrase>,
+    slop: u32,
+    proximity: f64,
+    k1: Option<f64>,
+    b: Option<f64>,
+    prefixes: Vec<Vec<u8>>,
+    max_expansions: usize,
+    fuzzy: Vec<Vec<u8>>,
+    fuzziness: u8,
+    fuzzy_discount: f64,
+    expansions: Vec<([u8; WIDTH], Vec<u8>)>,
 }
 
 impl Query {
@@ -109,7 +165,268 @@ impl Query {
         if !internal.is_sorted_by(|l, r| l < r) {
             return None;
         }
-        Some(Self { internal })
+        Some(Self {
+            weights: vec![1.0; internal.len()],
+            internal,
+            must: Vec::new(),
+            must_not: Vec::new(),
+            minimum_should_match: 0,
+            phrases: Vec::new(),
+            slop: 0,
+            proximity: 0.0,
+            k1: None,
+            b: None,
+            prefixes: Vec::new(),
+            max_expansions: 0,
+            fuzzy: Vec::new(),
+            fuzziness: 0,
+            fuzzy_discount: 1.0,
+            expansions: Vec::new(),
+        })
+    }
+
+    /// Adds terms that every result must contain, which also contribute to
+    /// scores, and terms that no result may contain.
+    pub fn with_clauses(self, must: Vec<[u8; WIDTH]>, must_not: Vec<[u8; WIDTH]>) -> Self {
+        assert!(must.is_sorted_by(|l, r| l < r), "invalid data");
+        assert!(must_not.is_sorted_by(|l, r| l < r), "invalid data");
+        let mut internal = self.internal.clone();
+        internal.extend_from_slice(&must);
+        internal.sort_unstable();
+        internal.dedup();
+        let weights = internal
+            .iter()
+            .map(|key| match self.internal.binary_search(key) {
+                Ok(i) => self.weights[i],
+                Err(_) => 1.0,
+            })
+            .collect();
+        let mut must = self.must.into_iter().chain(must).collect::<Vec<_>>();
+        must.sort_unstable();
+        must.dedup();
+        let mut must_not = self
+            .must_not
+            .into_iter()
+            .chain(must_not)
+            .collect::<Vec<_>>();
+        must_not.sort_unstable();
+        must_not.dedup();
+        Self {
+            internal,
+            weights,
+            must,
+            must_not,
+            minimum_should_match: self.minimum_should_match,
+            phrases: self.phrases,
+            slop: self.slop,
+            proximity: self.proximity,
+            k1: self.k1,
+            b: self.b,
+            prefixes: self.prefixes,
+            max_expansions: self.max_expansions,
+            fuzzy: self.fuzzy,
+            fuzziness: self.fuzziness,
+            fuzzy_discount: self.fuzzy_discount,
+            expansions: self.expansions,
+        }
+    }
+
+    /// Requires every result to contain all phrases, allowing the terms of a
+    /// phrase to spread over at most `slop` more positions. Terms of phrases
+    /// become `must` terms.
+    pub fn with_phrases(self, phrases: Vec<Phrase>, slop: u32) -> Self {
+        let mut must = phrases
+            .iter()
+            .flat_map(|phrase| phrase.keys())
+            .collect::<Vec<_>>();
+        must.sort_unstable();
+        must.dedup();
+        let mut result = self.with_clauses(must, Vec::new());
+        result.phrases.extend(phrases);
+        result.slop = slop;
+        result
+    }
+
+    /// Multiplies the contribution of each term by its weight.
+    pub fn with_weights(self, weights: Vec<f64>) -> Self {
+        assert_eq!(self.internal.len(), weights.len(), "invalid data");
+        Self { weights, ..self }
+    }
+
+    /// Requires every result to contain at least `minimum_should_match` of
+    /// the terms that are not `must` terms.
+    pub fn with_minimum_should_match(self, minimum_should_match: usize) -> Self {
+        Self {
+            minimum_should_match,
+            ..self
+        }
+    }
+
+    /// Adds to scores how close the terms occur to each other, multiplied by
+    /// `proximity`.
+    pub fn with_proximity(self, proximity: f64) -> Self {
+        Self { proximity, ..self }
+    }
+
+    /// Overrides `k1` and `b` of the index.
+    pub fn with_parameters(self, k1: Option<f64>, b: Option<f64>) -> Self {
+        Self { k1, b, ..self }
+    }
+
+    /// Adds prefixes, each of which stands for at most `max_expansions` terms
+    /// of the index beginning with it once the query is expanded.
+    pub fn with_prefixes(self, prefixes: Vec<Vec<u8>>, max_expansions: usize) -> Self {
+        Self {
+            prefixes,
+            max_expansions,
+            ..self
+        }
+    }
+
+    /// Makes the given terms match terms of the index within `fuzziness`
+    /// edits once the query is expanded, whose weights are multiplied by
+    /// `fuzzy_discount` for each edit.
+    pub fn with_fuzziness(self, fuzzy: Vec<Vec<u8>>, fuzziness: u8, fuzzy_discount: f64) -> Self {
+        Self {
+            fuzzy,
+            fuzziness,
+            fuzzy_discount,
+            ..self
+        }
+    }
+
+    /// Replaces prefixes and fuzzy terms with the terms they are expanded to,
+    /// which are scored as optional terms with the given weights.
+    pub fn with_expansions(self, expansions: Vec<([u8; WIDTH], Vec<u8>, f64)>) -> Self {
+        let mut internal = self.internal.clone();
+        internal.extend(expansions.iter().map(|(key, _, _)| *key));
+        internal.sort_unstable();
+        internal.dedup();
+        let weights = internal
+            .iter()
+            .map(|key| match self.internal.binary_search(key) {
+                Ok(i) => self.weights[i],
+                Err(_) => expansions
+                    .iter()
+                    .filter(|(k, _, _)| k == key)
+                    .map(|&(_, _, weight)| weight)
+                    .fold(0.0, f64::max),
+            })
+            .collect();
+        let mut expansions = expansions
+            .into_iter()
+            .map(|(key, term, _)| (key, term))
+            .collect::<Vec<_>>();
+        expansions.sort_unstable();
+        expansions.dedup();
+        Self {
+            internal,
+            weights,
+            prefixes: Vec::new(),
+            fuzzy: Vec::new(),
+            fuzziness: 0,
+            expansions,
+            ..self
+        }
+    }
+
+    #[inline(always)]
+    pub fn must(&self) -> &[[u8; WIDTH]] {
+        self.must.as_slice()
+    }
+
+    #[inline(always)]
+    pub fn must_not(&self) -> &[[u8; WIDTH]] {
+        self.must_not.as_slice()
+    }
+
+    #[inline(always)]
+    pub fn weights(&self) -> &[f64] {
+        self.weights.as_slice()
+    }
+
+    #[inline(always)]
+    pub fn minimum_should_match(&self) -> usize {
+        self.minimum_should_match
+    }
+
+    #[inline(always)]
+    pub fn phrases(&self) -> &[Phrase] {
+        self.phrases.as_slice()
+    }
+
+    #[inline(always)]
+    pub fn slop(&self) -> u32 {
+        self.slop
+    }
+
+    #[inline(always)]
+    pub fn proximity(&self) -> f64 {
+        self.proximity
+    }
+
+    #[inline(always)]
+    pub fn k1(&self) -> Option<f64> {
+        self.k1
+    }
+
+    #[inline(always)]
+    pub fn b(&self) -> Option<f64> {
+        self.b
+    }
+
+    #[inline(always)]
+    pub fn prefixes(&self) -> &[Vec<u8>] {
+        self.prefixes.as_slice()
+    }
+
+    #[inline(always)]
+    pub fn max_expansions(&self) -> usize {
+        self.max_expansions
+    }
+
+    #[inline(always)]
+    pub fn fuzzy(&self) -> &[Vec<u8>] {
+        self.fuzzy.as_slice()
+    }
+
+    #[inline(always)]
+    pub fn fuzziness(&self) -> u8 {
+        self.fuzziness
+    }
+
+    #[inline(always)]
+    pub fn fuzzy_discount(&self) -> f64 {
+        self.fuzzy_discount
+    }
+
+    /// Returns the terms that prefixes and fuzzy terms are expanded to, with
+    /// their strings.
+    #[inline(always)]
+    pub fn expansions(&self) -> &[([u8; WIDTH], Vec<u8>)] {
+        self.expansions.as_slice()
+    }
+
+    /// Returns whether scoring or matching reads positions of terms.
+    #[inline(always)]
+    pub fn positional(&self) -> bool {
+        !self.phrases.is_empty() || self.proximity != 0.0
+    }
+
+    #[inline(always)]
+    pub fn is_should(&self, key: &[u8; WIDTH]) -> bool {
+        self.internal.binary_search(key).is_ok() && self.must.binary_search(key).is_err()
+    }
+
+    /// Returns the constraints of `must` and `must_not` terms, if any.
+    pub fn clauses(&self) -> Option<Expression> {
+        let must = self.must.iter().map(|&key| Expression::Token(key));
+        let must_not = self
+            .must_not
+            .iter()
+            .map(|&key| Expression::Not(Box::new(Expression::Token(key))));
+        must.chain(must_not)
+            .reduce(|l, r| Expression::And(Box::new(l), Box::new(r)))
     }
 
     #[inline(always)]
@@ -132,3 +449,160 @@ impl Query {
         self.internal.iter()
     }
 }
+
+/// A sequence of terms, each with its offset from the first term.
+#[derive(Debug, Clone)]
+pub struct Phrase {
+    terms: Vec<([u8; WIDTH], u32)>,
+}
+
+impl Phrase {
+    pub fn new(terms: Vec<([u8; WIDTH], u32)>) -> Self {
+        assert!(!terms.is_empty(), "invalid data");
+        assert!(
+            terms.is_sorted_by_key(|&(_, offset)| offset),
+            "invalid data"
+        );
+        let base = terms[0].1;
+        let terms = terms
+            .into_iter()
+            .map(|(key, offset)| (key, offset - base))
+            .collect();
+        Self { terms }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = [u8; WIDTH]> {
+        self.terms.iter().map(|&(key, _)| key)
+    }
+
+    /// Returns whether the terms occur in order, each at least as far from
+    /// the previous one as in the phrase, spreading over at most `slop` more
+    /// positions than the phrase.
+    pub fn matches(&self, slop: u32, positions: &mut impl FnMut(&[u8; WIDTH]) -> Vec<u32>) -> bool {
+        let lists = self
+            .terms
+            .iter()
+            .map(|(key, _)| positions(key))
+            .collect::<Vec<_>>();
+        let span = self.terms[self.terms.len() - 1].1 as u64 + slop as u64;
+        'start: for &start in lists[0].iter() {
+            let mut current = start as u64;
+            for i in 1..self.terms.len() {
+                // the earliest position is the best for every later term
+                let target = current + (self.terms[i].1 - self.terms[i - 1].1) as u64;
+                let j = lists[i].partition_point(|&x| (x as u64) < target);
+                let Some(&x) = lists[i].get(j) else {
+                    break 'start;
+                };
+                current = x as u64;
+            }
+            if current - start as u64 <= span {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// A boolean expression over tokens, evaluated in three-valued logic, where
+/// `None` means that the index cannot decide and the row must be rechecked.
+#[derive(Debug, Clone)]
+pub enum Expression {
+    Constant(Option<bool>),
+    Token([u8; WIDTH]),
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    pub fn evaluate(&self, contains: &mut impl FnMut(&[u8; WIDTH]) -> bool) -> Option<bool> {
+        match self {
+            Expression::Constant(value) => *value,
+            Expression::Token(key) => Some(contains(key)),
+            Expression::Not(x) => x.evaluate(contains).map(|x| !x),
+            Expression::And(l, r) => match l.evaluate(contains) {
+                Some(false) => Some(false),
+                Some(true) => r.evaluate(contains),
+                None => match r.evaluate(contains) {
+                    Some(false) => Some(false),
+                    _ => None,
+                },
+            },
+            Expression::Or(l, r) => match l.evaluate(contains) {
+                Some(true) => Some(true),
+                Some(false) => r.evaluate(contains),
+                None => match r.evaluate(contains) {
+                    Some(true) => Some(true),
+                    _ => None,
+                },
+            },
+        }
+    }
+
+    /// Returns whether every decision of the expression is definite.
+    pub fn is_exact(&self) -> bool {
+        match self {
+            Expression::Constant(value) => value.is_some(),
+            Expression::Token(_) => true,
+            Expression::Not(x) => x.is_exact(),
+            Expression::And(l, r) | Expression::Or(l, r) => l.is_exact() && r.is_exact(),
+        }
+    }
+
+    /// Returns sorted and deduplicated tokens in the expression.
+    pub fn tokens(&self) -> Vec<[u8; WIDTH]> {
+        fn visit(this: &Expression, result: &mut Vec<[u8; WIDTH]>) {
+            match this {
+                Expression::Constant(_) => (),
+                Expression::Token(key) => result.push(*key),
+                Expression::Not(x) => visit(x, result),
+                Expression::And(l, r) | Expression::Or(l, r) => {
+                    visit(l, result);
+                    visit(r, result);
+                }
+            }
+        }
+        let mut result = Vec::new();
+        visit(self, &mut result);
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+}
+
+#[test]
+fn expression_evaluate() {
+    use Expression::*;
+    let a = || Box::new(Token([1; WIDTH]));
+    let b = || Box::new(Token([2; WIDTH]));
+    let maybe = || Box::new(Constant(None));
+    let mut contains = |key: &[u8; WIDTH]| key[0] == 1;
+    assert_eq!(
+        And(a(), Box::new(Not(b()))).evaluate(&mut contains),
+        Some(true)
+    );
+    assert_eq!(And(b(), maybe()).evaluate(&mut contains), Some(false));
+    assert_eq!(And(maybe(), a()).evaluate(&mut contains), None);
+    assert_eq!(Or(maybe(), a()).evaluate(&mut contains), Some(true));
+    assert_eq!(Or(b(), maybe()).evaluate(&mut contains), None);
+    assert_eq!(Not(maybe()).evaluate(&mut contains), None);
+    assert!(!And(a(), maybe()).is_exact());
+    assert_eq!(Or(b(), a()).tokens(), vec![[1; WIDTH], [2; WIDTH]]);
+}
+
+#[test]
+fn phrase_matches() {
+    let (a, b, c) = ([1; WIDTH], [2; WIDTH], [3; WIDTH]);
+    let mut positions = |key: &[u8; WIDTH]| match key[0] {
+        1 => vec![1, 7],
+        2 => vec![2, 5],
+        _ => vec![9],
+    };
+    assert!(Phrase::new(vec![(a, 0), (b, 1)]).matches(0, &mut positions));
+    assert!(!Phrase::new(vec![(b, 0), (c, 1)]).matches(0, &mut positions));
+    assert!(Phrase::new(vec![(b, 0), (c, 1)]).matches(3, &mut positions));
+    assert!(!Phrase::new(vec![(a, 0), (b, 2)]).matches(1, &mut positions));
+    assert!(Phrase::new(vec![(a, 0), (b, 2)]).matches(2, &mut positions));
+    assert!(Phrase::new(vec![(a, 4), (c, 6)]).matches(0, &mut positions));
+}
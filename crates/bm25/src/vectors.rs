@@ -0,0 +1,134 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::Opaque;
+use crate::tuples::*;
+use crate::vector::Element;
+use index::relation::{Page, RelationRead, RelationWrite};
+
+/// A live document that is not sealed yet.
+pub struct Unsealed<'a> {
+    pub fieldnorm: u8,
+    pub elements: &'a [Element],
+    /// The compressed positions of each element, one after another.
+    pub positions: &'a [u8],
+    /// The strings of hashed keys, as encoded by `dictionary::encode`.
+    pub strings: &'a [u8],
+    pub payload: [u16; 3],
+}
+
+/// Calls `f` with each live document that is not sealed yet.
+pub fn read<R: RelationRead>(index: &R, ptr_vectors: u32, mut f: impl FnMut(Unsealed<'_>))
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut parser = Parser::default();
+    let mut current = ptr_vectors;
+    while current != u32::MAX {
+        let vector_guard = index.read(current);
+        parser.parse(&*vector_guard, &mut f);
+        current = vector_guard.get_opaque().next;
+    }
+}
+
+/// Calls `f` with each live document that is not sealed yet, as `read` does,
+/// and returns the last page locked for writing, so that no document is
+/// appended until it is dropped. `check` is called before each page.
+pub fn seal<R: RelationRead + RelationWrite>(
+    index: &R,
+    ptr_vectors: u32,
+    check: impl Fn(),
+    mut f: impl FnMut(Unsealed<'_>),
+) -> R::WriteGuard<'_>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut parser = Parser::default();
+    let mut current = ptr_vectors;
+    loop {
+        check();
+        let read = index.read(current);
+        if read.get_opaque().next != u32::MAX {
+            parser.parse(&*read, &mut f);
+            current = read.get_opaque().next;
+            continue;
+        }
+        drop(read);
+        // a page may be appended before the lock is taken
+        let write = index.write(current);
+        parser.parse(&*write, &mut f);
+        if write.get_opaque().next == u32::MAX {
+            break write;
+        }
+        current = write.get_opaque().next;
+    }
+}
+
+/// The document whose tuples are being read, which continue across pages.
+#[derive(Default)]
+struct Parser {
+    state: Option<(u8, Vec<Element>, Vec<u8>, Vec<u8>)>,
+}
+
+impl Parser {
+    fn parse<P: Page>(&mut self, page: &P, f: &mut impl FnMut(Unsealed<'_>)) {
+        for i in 1..=page.len() {
+            let vector_bytes = page.get(i).expect("data corruption");
+            let vector_tuple = VectorTuple::deserialize_ref(vector_bytes);
+            match vector_tuple {
+                VectorTupleReader::_2(vector_tuple) => {
+                    self.state =
+                        Some((vector_tuple.fieldnorm(), Vec::new(), Vec::new(), Vec::new()));
+                }
+                VectorTupleReader::_3(vector_tuple) => {
+                    if let Some((_, _, positions, _)) = self.state.as_mut() {
+                        positions.extend_from_slice(vector_tuple.positions());
+                    } else {
+                        panic!("data corruption");
+                    }
+                }
+                VectorTupleReader::_4(vector_tuple) => {
+                    if let Some((_, _, _, strings)) = self.state.as_mut() {
+                        strings.extend_from_slice(vector_tuple.terms());
+                    } else {
+                        panic!("data corruption");
+                    }
+                }
+                VectorTupleReader::_1(vector_tuple) => {
+                    if let Some((_, elements, _, _)) = self.state.as_mut() {
+                        elements.extend_from_slice(vector_tuple.elements());
+                    } else {
+                        panic!("data corruption");
+                    }
+                }
+                VectorTupleReader::_0(vector_tuple) => {
+                    if let Some((fieldnorm, mut elements, positions, strings)) = self.state.take() {
+                        if !bool::from(vector_tuple.deleted()) {
+                            elements.extend_from_slice(vector_tuple.elements());
+                            f(Unsealed {
+                                fieldnorm,
+                                elements: &elements,
+                                positions: &positions,
+                                strings: &strings,
+                                payload: vector_tuple.payload(),
+                            });
+                        }
+                    } else {
+                        panic!("data corruption");
+                    }
+                }
+            }
+        }
+    }
+}
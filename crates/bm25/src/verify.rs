@@ -0,0 +1,1123 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::bm25::{Wand, length_to_fieldnorm};
+use crate::tuples::*;
+use crate::vector::{Document, is_hashed};
+use crate::{Dictionary, Opaque, WIDTH, compression};
+use index::relation::{Opaque as _, Page, RelationLength, RelationRead};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+// Readers of the index assume that it is consistent and panic on corruption,
+// so the checker copies each page that it walks and runs deserialization and
+// decompression, which touch nothing but the copied bytes, in `catch_unwind`,
+// reporting a problem instead.
+
+pub struct Problem {
+    pub region: &'static str,
+    pub page: Option<u32>,
+    pub message: String,
+}
+
+/// Checks every tape of the index, the address trees of segments and the
+/// consistency of tokens, summaries, blocks and documents, returning the
+/// problems found. The payloads of live documents are pushed to `payloads`
+/// if it is given. The index must not be modified while it is checked.
+pub fn verify<R: RelationRead + RelationLength>(
+    index: &R,
+    mut payloads: Option<&mut Vec<[u16; 3]>>,
+) -> Vec<Problem>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    let mut verifier = Verifier {
+        index,
+        number_of_pages: index.len(),
+        positions: false,
+        terms: None,
+        owners: HashMap::new(),
+        problems: Vec::new(),
+    };
+
+    let meta = verifier.tape("meta", 0);
+    let Some((location, bytes)) = meta.tuples.first() else {
+        verifier.report("meta", Some(0), "meta tuple is missing".to_string());
+        return verifier.problems;
+    };
+    let Some((ptr_lock, ptr_jump, positions, dictionary)) =
+        verifier.decode("meta", *location, bytes, |bytes| {
+            let meta_tuple = MetaTuple::deserialize_ref(bytes);
+            (
+                meta_tuple.ptr_lock(),
+                meta_tuple.ptr_jump(),
+                meta_tuple.positions(),
+                meta_tuple.dictionary(),
+            )
+        })
+    else {
+        return verifier.problems;
+    };
+    verifier.positions = positions;
+    verifier.tape("lock", ptr_lock);
+
+    let jump = verifier.tape("jump", ptr_jump);
+    let Some((location, bytes)) = jump.tuples.first() else {
+        verifier.report("jump", Some(ptr_jump), "jump tuple is missing".to_string());
+        return verifier.problems;
+    };
+    let Some((ptr_vectors, ptr_segments, terms, strings)) =
+        verifier.decode("jump", *location, bytes, |bytes| {
+            let jump_tuple = JumpTuple::deserialize_ref(bytes);
+            (
+                jump_tuple.ptr_vectors(),
+                jump_tuple.ptr_segments(),
+                (
+                    jump_tuple.ptr_terms(),
+                    jump_tuple.depth_terms(),
+                    jump_tuple.start_terms(),
+                    jump_tuple.free_terms(),
+                ),
+                (
+                    jump_tuple.ptr_strings(),
+                    jump_tuple.depth_strings(),
+                    jump_tuple.start_strings(),
+                    jump_tuple.free_strings(),
+                ),
+            )
+        })
+    else {
+        return verifier.problems;
+    };
+
+    if dictionary {
+        let (ptr_terms, depth_terms, start_terms, free_terms) = terms;
+        let mut terms = BTreeMap::new();
+        let mut keys = Vec::new();
+        let tape = verifier.tape("terms", ptr_terms);
+        for (location, bytes) in tape.tuples.iter() {
+            let Some((key, term)) = verifier.decode("terms", *location, bytes, |bytes| {
+                let term_tuple = TermTuple::deserialize_ref(bytes);
+                (term_tuple.key(), term_tuple.term().to_vec())
+            }) else {
+                continue;
+            };
+            if !is_hashed(&key) {
+                verifier.report(
+                    "terms",
+                    Some(location.0),
+                    format!("term {:?} is stored but not hashed", lossy(&term)),
+                );
+            }
+            if terms.last_key_value().is_some_and(|(&last, _)| last >= key) {
+                verifier.report(
+                    "terms",
+                    Some(location.0),
+                    "terms are not in order".to_string(),
+                );
+            }
+            terms.insert(key, term);
+            keys.push((location.0, key));
+        }
+        verifier.tape("addresses", free_terms);
+        verifier.address_tree(
+            "the dictionary",
+            "terms",
+            &tape,
+            &keys,
+            depth_terms,
+            start_terms,
+        );
+
+        let (ptr_strings, depth_strings, start_strings, free_strings) = strings;
+        let mut last = None::<Vec<u8>>;
+        let mut number_of_strings = 0_usize;
+        let mut prefixes = Vec::new();
+        let tape = verifier.tape("strings", ptr_strings);
+        for (location, bytes) in tape.tuples.iter() {
+            let Some((prefix, key, term)) = verifier.decode("strings", *location, bytes, |bytes| {
+                let string_tuple = StringTuple::deserialize_ref(bytes);
+                (
+                    string_tuple.prefix(),
+                    string_tuple.key(),
+                    string_tuple.term().to_vec(),
+                )
+            }) else {
+                continue;
+            };
+            if prefix != crate::dictionary::prefix(&term) {
+                verifier.report(
+                    "strings",
+                    Some(location.0),
+                    format!("string {:?} is stored with another prefix", lossy(&term)),
+                );
+            }
+            if terms.get(&key) != Some(&term) {
+                verifier.report(
+                    "strings",
+                    Some(location.0),
+                    format!("string {:?} is not a term of its key", lossy(&term)),
+                );
+            }
+            if last.as_ref().is_some_and(|last| *last >= term) {
+                verifier.report(
+                    "strings",
+                    Some(location.0),
+                    "strings are not in order".to_string(),
+                );
+            }
+            last = Some(term);
+            number_of_strings += 1;
+            prefixes.push((location.0, prefix));
+        }
+        if number_of_strings != terms.len() {
+            let message = format!(
+                "the dictionary has {} terms but {number_of_strings} strings",
+                terms.len()
+            );
+            verifier.report("strings", None, message);
+        }
+        verifier.tape("addresses", free_strings);
+        verifier.address_tree(
+            "the dictionary",
+            "strings",
+            &tape,
+            &prefixes,
+            depth_strings,
+            start_strings,
+        );
+        verifier.terms = Some(terms);
+    }
+
+    let tape = verifier.tape("segments", ptr_segments);
+    for (n, (location, bytes)) in tape.tuples.iter().enumerate() {
+        if let Some(segment) =
+            verifier.decode("segments", *location, bytes, crate::segments::deserialize)
+        {
+            verifier.segment(n, &segment, payloads.as_deref_mut());
+        }
+    }
+
+    verifier.vectors(ptr_vectors, payloads);
+
+    verifier.problems
+}
+
+type Location = (u32, u16);
+
+struct Tape {
+    /// Each page with its number of tuples.
+    pages: Vec<(u32, u16)>,
+    tuples: Vec<(Location, Vec<u8>)>,
+}
+
+struct Pending {
+    fieldnorm: u8,
+    elements: Vec<crate::vector::Element>,
+    positions: Vec<u8>,
+    terms: Vec<u8>,
+}
+
+struct Verifier<'a, R> {
+    index: &'a R,
+    number_of_pages: u32,
+    positions: bool,
+    terms: Option<BTreeMap<[u8; WIDTH], Vec<u8>>>,
+    /// The region of each page that is walked, so that a page is walked once.
+    owners: HashMap<u32, &'static str>,
+    problems: Vec<Problem>,
+}
+
+impl<R: RelationRead + RelationLength> Verifier<'_, R>
+where
+    R::Page: Page<Opaque = Opaque>,
+{
+    fn report(&mut self, region: &'static str, page: Option<u32>, message: String) {
+        self.problems.push(Problem {
+            region,
+            page,
+            message,
+        });
+    }
+
+    fn attempt<T>(
+        &mut self,
+        region: &'static str,
+        page: u32,
+        what: &str,
+        f: impl FnOnce() -> T,
+    ) -> Option<T> {
+        match catch_unwind(AssertUnwindSafe(f)) {
+            Ok(result) => Some(result),
+            Err(_) => {
+                self.report(region, Some(page), format!("{what} is corrupted"));
+                None
+            }
+        }
+    }
+
+    fn decode<T>(
+        &mut self,
+        region: &'static str,
+        (page, i): Location,
+        bytes: &[u8],
+        f: impl FnOnce(&[u8]) -> T,
+    ) -> Option<T> {
+        self.attempt(region, page, &format!("tuple {i}"), || f(bytes))
+    }
+
+    /// Copies the tuples of a page and returns them with the next page.
+    fn load(&mut self, region: &'static str, id: u32) -> Option<(u32, Vec<(u16, Vec<u8>)>)> {
+        if id >= self.number_of_pages {
+            self.report(region, Some(id), "page does not exist".to_string());
+            return None;
+        }
+        let guard = self.index.read(id);
+        let copied = self.attempt(region, id, "page", || {
+            let opaque = *guard.get_opaque();
+            let tuples = (1..=guard.len())
+                .map(|i| (i, guard.get(i).map(<[u8]>::to_vec)))
+                .collect::<Vec<_>>();
+            (opaque, tuples)
+        });
+        drop(guard);
+        let (opaque, tuples) = copied?;
+        if opaque.is_deleted() {
+            self.report(region, Some(id), "page is recycled".to_string());
+            return None;
+        }
+        let mut result = Vec::with_capacity(tuples.len());
+        for (i, bytes) in tuples {
+            if let Some(bytes) = bytes {
+                result.push((i, bytes));
+            } else {
+                self.report(region, Some(id), format!("tuple {i} is missing"));
+            }
+        }
+        Some((opaque.next, result))
+    }
+
+    /// Walks a tape, stopping at a page that is walked already, which is
+    /// either shared by two tapes or on a cycle.
+    fn tape(&mut self, region: &'static str, first: u32) -> Tape {
+        let mut tape = Tape {
+            pages: Vec::new(),
+            tuples: Vec::new(),
+        };
+        let mut current = first;
+        while current != u32::MAX {
+            if let Some(owner) = self.owners.get(&current) {
+                let message = format!("page is already walked as a page of {owner}");
+                self.report(region, Some(current), message);
+                break;
+            }
+            let Some((next, tuples)) = self.load(region, current) else {
+                break;
+            };
+            self.owners.insert(current, region);
+            tape.pages.push((current, tuples.len() as u16));
+            tape.tuples
+                .extend(tuples.into_iter().map(|(i, bytes)| ((current, i), bytes)));
+            current = next;
+        }
+        tape
+    }
+
+    fn segment(
+        &mut self,
+        n: usize,
+        segment: &SegmentTuple,
+        mut payloads: Option<&mut Vec<[u16; 3]>>,
+    ) {
+        // documents
+
+        let tape_documents = self.tape("documents", segment.ptr_documents);
+        let mut fieldnorms = Vec::new();
+        let mut number_of_deleted_documents = 0_u32;
+        for (location, bytes) in tape_documents.tuples.iter() {
+            let Some((deleted, fieldnorm, payload)) =
+                self.decode("documents", *location, bytes, |bytes| {
+                    let document_tuple = DocumentTuple::deserialize_ref(bytes);
+                    (
+                        bool::from(document_tuple.deleted()),
+                        document_tuple.fieldnorm(),
+                        document_tuple.payload(),
+                    )
+                })
+            else {
+                continue;
+            };
+            if deleted {
+                number_of_deleted_documents += 1;
+            } else if let Some(payloads) = payloads.as_deref_mut() {
+                payloads.push(payload);
+            }
+            fieldnorms.push(fieldnorm);
+        }
+        if fieldnorms.len() != segment.number_of_documents as usize {
+            let message = format!(
+                "segment {n} has {} documents but records {}",
+                fieldnorms.len(),
+                segment.number_of_documents
+            );
+            self.report("documents", None, message);
+        }
+        if number_of_deleted_documents != segment.number_of_deleted_documents {
+            let message = format!(
+                "segment {n} has {number_of_deleted_documents} deleted documents but records {}",
+                segment.number_of_deleted_documents
+            );
+            self.report("documents", None, message);
+        }
+
+        // the address tree of documents, which finds a document by the digits
+        // of its ID, so every node but the last of each level must be full
+
+        self.tape("addresses", segment.free_documents);
+        let pages = if fieldnorms.is_empty() {
+            Vec::new()
+        } else {
+            let width_0 = segment.width_0_documents;
+            if let Some(((_, last), rest)) = tape_documents.pages.split_last()
+                && (rest.iter().any(|&(_, len)| len != width_0) || *last == 0 || *last > width_0)
+            {
+                let message = format!("segment {n} has pages of documents of different sizes");
+                self.report("documents", None, message);
+            }
+            tape_documents.pages.iter().map(|&(id, _)| id).collect()
+        };
+        let mut leaves = Vec::new();
+        let mut sizes = vec![Vec::new(); segment.depth_documents.min(32) as usize];
+        if segment.depth_documents > 32 {
+            let message = format!("segment {n} has an address tree of documents that is too deep");
+            self.report("addresses", None, message);
+        } else if segment.start_documents != u32::MAX {
+            let mut visited = HashSet::new();
+            self.address_documents(
+                segment.depth_documents,
+                segment.start_documents,
+                segment.width_1_documents,
+                &mut leaves,
+                &mut sizes,
+                &mut visited,
+            );
+        }
+        let full = sizes.iter().all(|sizes| {
+            let n = sizes.len().saturating_sub(1);
+            sizes[..n]
+                .iter()
+                .all(|&size| size == segment.width_1_documents as usize)
+        });
+        if leaves != pages || !full {
+            let message = format!(
+                "segment {n} has an address tree of documents that does not match its documents"
+            );
+            self.report("addresses", None, message);
+        }
+
+        // tokens and their address tree
+
+        let tape_tokens = self.tape("tokens", segment.ptr_tokens);
+        let mut tokens = Vec::<(Location, TokenTuple)>::new();
+        for (location, bytes) in tape_tokens.tuples.iter() {
+            let Some(token) = self.decode("tokens", *location, bytes, |bytes| {
+                let token_tuple = TokenTuple::deserialize_ref(bytes);
+                TokenTuple {
+                    id: token_tuple.id(),
+                    number_of_documents: token_tuple.number_of_documents(),
+                    sum_of_term_frequencies: token_tuple.sum_of_term_frequencies(),
+                    wand_fieldnorm: token_tuple.wand_fieldnorm(),
+                    wand_term_frequency: token_tuple.wand_term_frequency(),
+                    wptr_summaries: token_tuple.wptr_summaries(),
+                }
+            }) else {
+                continue;
+            };
+            if tokens.last().is_some_and(|(_, last)| last.id >= token.id) {
+                let message = format!("segment {n} has tokens that are not in order");
+                self.report("tokens", Some(location.0), message);
+            }
+            if token.number_of_documents == 0 {
+                let message = format!("segment {n} has a token without documents");
+                self.report("tokens", Some(location.0), message);
+            }
+            if let Some(terms) = self.terms.as_ref()
+                && is_hashed(&token.id)
+                && !terms.contains_key(&token.id)
+            {
+                let message =
+                    format!("segment {n} has a hashed token that is not in the dictionary");
+                self.report("terms", Some(location.0), message);
+            }
+            tokens.push((*location, token));
+        }
+
+        self.tape("addresses", segment.free_tokens);
+        let keys = tokens
+            .iter()
+            .map(|((page, _), token)| (*page, token.id))
+            .collect::<Vec<_>>();
+        self.address_tree(
+            &format!("segment {n}"),
+            "tokens",
+            &tape_tokens,
+            &keys,
+            segment.depth_tokens,
+            segment.start_tokens,
+        );
+
+        // summaries, blocks and positions
+
+        let tape_summaries = self.tape("summaries", segment.ptr_summaries);
+        let mut summaries = Vec::<(Location, SummaryTuple)>::new();
+        for (location, bytes) in tape_summaries.tuples.iter() {
+            if let Some(summary) = self.decode("summaries", *location, bytes, |bytes| {
+                let summary_tuple = SummaryTuple::deserialize_ref(bytes);
+                SummaryTuple {
+                    min_document_id: summary_tuple.min_document_id(),
+                    max_document_id: summary_tuple.max_document_id(),
+                    number_of_documents: summary_tuple.number_of_documents(),
+                    wand_fieldnorm: summary_tuple.wand_fieldnorm(),
+                    wand_term_frequency: summary_tuple.wand_term_frequency(),
+                    wptr_block: summary_tuple.wptr_block(),
+                }
+            }) {
+                summaries.push((*location, summary));
+            }
+        }
+        let tape_blocks = self.tape("blocks", segment.ptr_blocks);
+        let mut blocks = Vec::<(Location, BlockTuple)>::new();
+        for (location, bytes) in tape_blocks.tuples.iter() {
+            if let Some(block) = self.decode("blocks", *location, bytes, |bytes| {
+                let block_tuple = BlockTuple::deserialize_ref(bytes);
+                BlockTuple {
+                    metadata_document_ids: block_tuple.metadata_document_ids(),
+                    compressed_document_ids: block_tuple.compressed_document_ids().to_vec(),
+                    metadata_term_frequencies: block_tuple.metadata_term_frequencies(),
+                    compressed_term_frequencies: block_tuple.compressed_term_frequencies().to_vec(),
+                    wptr_positions: block_tuple.wptr_positions(),
+                }
+            }) {
+                blocks.push((*location, block));
+            }
+        }
+        let mut chunks = Vec::<(bool, Vec<u8>)>::new();
+        let mut offsets = HashMap::<Location, usize>::new();
+        if segment.ptr_positions != u32::MAX {
+            if !self.positions {
+                let message = format!("segment {n} has positions but the index does not");
+                self.report("positions", None, message);
+            }
+            let tape_positions = self.tape("positions", segment.ptr_positions);
+            for (location, bytes) in tape_positions.tuples.iter() {
+                if let Some(chunk) = self.decode("positions", *location, bytes, |bytes| {
+                    let positions_tuple = PositionsTuple::deserialize_ref(bytes);
+                    (positions_tuple.last(), positions_tuple.bytes().to_vec())
+                }) {
+                    offsets.insert(*location, chunks.len());
+                    chunks.push(chunk);
+                }
+            }
+        } else if self.positions {
+            let message = format!("segment {n} has no positions but the index does");
+            self.report("positions", None, message);
+        }
+
+        let mut lengths = vec![0_u64; fieldnorms.len()];
+        let mut cursor = 0_usize;
+        for (token_location, token) in tokens.iter() {
+            let count = token.number_of_documents.div_ceil(128) as usize;
+            if cursor + count > summaries.len().min(blocks.len()) {
+                break;
+            }
+            if summaries[cursor].0 != token.wptr_summaries {
+                let message =
+                    format!("segment {n} has a token that does not point to its summaries");
+                self.report("tokens", Some(token_location.0), message);
+            }
+            let mut wand = Wand::new();
+            let mut number_of_documents = 0_u32;
+            let mut sum_of_term_frequencies = 0_u64;
+            let mut last = None;
+            for ((location, summary), (block_location, block)) in summaries[cursor..cursor + count]
+                .iter()
+                .zip(blocks[cursor..cursor + count].iter())
+            {
+                if summary.wptr_block.into_inner() != *block_location {
+                    let message =
+                        format!("segment {n} has a summary that does not point to its block");
+                    self.report("summaries", Some(location.0), message);
+                }
+                let Some((document_ids, term_frequencies)) = self.attempt(
+                    "blocks",
+                    block_location.0,
+                    &format!("tuple {}", block_location.1),
+                    || {
+                        let mut document_ids = compression::Decompressed::new();
+                        compression::decompress_document_ids(
+                            summary.min_document_id,
+                            block.metadata_document_ids,
+                            &block.compressed_document_ids,
+                            &mut document_ids,
+                        );
+                        let mut term_frequencies = compression::Decompressed::new();
+                        compression::decompress_term_frequencies(
+                            block.metadata_term_frequencies,
+                            &block.compressed_term_frequencies,
+                            &mut term_frequencies,
+                        );
+                        (
+                            document_ids.as_slice().to_vec(),
+                            term_frequencies.as_slice().to_vec(),
+                        )
+                    },
+                ) else {
+                    continue;
+                };
+                if document_ids.len() != summary.number_of_documents as usize
+                    || term_frequencies.len() != document_ids.len()
+                {
+                    let message =
+                        format!("segment {n} has a block that does not match its summary");
+                    self.report("blocks", Some(block_location.0), message);
+                    continue;
+                }
+                if !document_ids.is_sorted_by(|l, r| l < r)
+                    || last >= document_ids.first().copied()
+                    || document_ids.first() != Some(&summary.min_document_id)
+                    || document_ids.last() != Some(&summary.max_document_id)
+                {
+                    let message =
+                        format!("segment {n} has a block whose documents do not match its summary");
+                    self.report("blocks", Some(block_location.0), message);
+                }
+                let mut block_wand = Wand::new();
+                for (&document_id, &term_frequency) in
+                    document_ids.iter().zip(term_frequencies.iter())
+                {
+                    if let Some(&fieldnorm) = fieldnorms.get(document_id as usize)
+                        && term_frequency != 0
+                    {
+                        block_wand.push(fieldnorm, term_frequency);
+                        lengths[document_id as usize] += term_frequency as u64;
+                    } else {
+                        let message =
+                            format!("segment {n} has a bad posting of document {document_id}");
+                        self.report("blocks", Some(block_location.0), message);
+                    }
+                    sum_of_term_frequencies += term_frequency as u64;
+                }
+                if (block_wand.fieldnorm(), block_wand.term_frequency())
+                    != (summary.wand_fieldnorm, summary.wand_term_frequency)
+                {
+                    let message = format!(
+                        "segment {n} has a summary whose upper bound does not match its block"
+                    );
+                    self.report("summaries", Some(location.0), message);
+                }
+                wand.extend(&block_wand);
+                number_of_documents += document_ids.len() as u32;
+                last = document_ids.last().copied();
+                self.block_positions(
+                    n,
+                    *block_location,
+                    block.wptr_positions.into_inner(),
+                    document_ids.len(),
+                    &chunks,
+                    &offsets,
+                );
+            }
+            if number_of_documents != token.number_of_documents
+                || sum_of_term_frequencies != token.sum_of_term_frequencies
+                || (wand.fieldnorm(), wand.term_frequency())
+                    != (token.wand_fieldnorm, token.wand_term_frequency)
+            {
+                let message = format!("segment {n} has a token that does not match its blocks");
+                self.report("tokens", Some(token_location.0), message);
+            }
+            cursor += count;
+        }
+        if cursor != summaries.len() || cursor != blocks.len() {
+            let message = format!(
+                "segment {n} has {} summaries and {} blocks but its tokens have {cursor}",
+                summaries.len(),
+                blocks.len()
+            );
+            self.report("summaries", None, message);
+        }
+
+        let sum_of_document_lengths = lengths.iter().sum::<u64>();
+        if sum_of_document_lengths != segment.sum_of_document_lengths {
+            let message = format!(
+                "segment {n} has documents of total length {} but records {}",
+                sum_of_document_lengths, segment.sum_of_document_lengths
+            );
+            self.report("documents", None, message);
+        }
+        let mismatched = fieldnorms
+            .iter()
+            .zip(lengths.iter())
+            .filter(|&(&fieldnorm, &length)| {
+                fieldnorm != length_to_fieldnorm(length.min(u32::MAX as u64) as u32)
+            })
+            .count();
+        if mismatched != 0 {
+            let message = format!(
+                "segment {n} has {mismatched} documents whose lengths do not match their postings"
+            );
+            self.report("documents", None, message);
+        }
+    }
+
+    fn address_documents(
+        &mut self,
+        depth: u32,
+        id: u32,
+        width_1: u16,
+        leaves: &mut Vec<u32>,
+        sizes: &mut [Vec<usize>],
+        visited: &mut HashSet<u32>,
+    ) {
+        if depth == 0 {
+            leaves.push(id);
+            return;
+        }
+        if self.owners.get(&id) != Some(&"addresses") || !visited.insert(id) {
+            self.report(
+                "addresses",
+                Some(id),
+                "page is not a node of the address tree".to_string(),
+            );
+            return;
+        }
+        let Some((_, tuples)) = self.load("addresses", id) else {
+            return;
+        };
+        let Some((i, bytes)) = tuples.first() else {
+            self.report("addresses", Some(id), "tuple 1 is missing".to_string());
+            return;
+        };
+        let Some(internal) = self.decode("addresses", (id, *i), bytes, |bytes| {
+            AddressDocumentsTuple::deserialize_ref(bytes)
+                .internal()
+                .to_vec()
+        }) else {
+            return;
+        };
+        if internal.is_empty() || internal.len() > width_1 as usize {
+            self.report(
+                "addresses",
+                Some(id),
+                "node of the address tree has a wrong number of children".to_string(),
+            );
+        }
+        sizes[depth as usize - 1].push(internal.len());
+        for child in internal {
+            self.address_documents(depth - 1, child, width_1, leaves, sizes, visited);
+        }
+    }
+
+    /// Checks that the leaves of an address tree written by `address_tokens`
+    /// are the pages of the tape in order, each with its last key.
+    fn address_tree(
+        &mut self,
+        owner: &str,
+        what: &str,
+        tape: &Tape,
+        keys: &[(u32, [u8; WIDTH])],
+        depth: u32,
+        start: u32,
+    ) {
+        let expected = keys.iter().copied().collect::<HashMap<_, _>>();
+        let pages = tape
+            .pages
+            .iter()
+            .filter(|&&(_, len)| len != 0)
+            .map(|&(id, _)| id)
+            .collect::<Vec<_>>();
+        let mut leaves = Vec::new();
+        if depth > 32 {
+            let message = format!("{owner} has an address tree of {what} that is too deep");
+            self.report("addresses", None, message);
+        } else if start != u32::MAX {
+            let mut visited = HashSet::new();
+            self.address_tokens(depth, start, None, &mut leaves, &mut visited);
+        }
+        let matched = leaves.iter().map(|&(id, _)| id).eq(pages.iter().copied())
+            && leaves
+                .iter()
+                .all(|(id, key)| key.is_none_or(|key| expected.get(id) == Some(&key)));
+        if !matched {
+            let message =
+                format!("{owner} has an address tree of {what} that does not match its {what}");
+            self.report("addresses", None, message);
+        }
+    }
+
+    /// Collects the leaves of the address tree of tokens with the keys of
+    /// their edges, each of which is the last key of its child.
+    fn address_tokens(
+        &mut self,
+        depth: u32,
+        id: u32,
+        key: Option<[u8; WIDTH]>,
+        leaves: &mut Vec<(u32, Option<[u8; WIDTH]>)>,
+        visited: &mut HashSet<u32>,
+    ) {
+        if depth == 0 {
+            leaves.push((id, key));
+            return;
+        }
+        if self.owners.get(&id) != Some(&"addresses") || !visited.insert(id) {
+            self.report(
+                "addresses",
+                Some(id),
+                "page is not a node of the address tree".to_string(),
+            );
+            return;
+        }
+        let Some((_, tuples)) = self.load("addresses", id) else {
+            return;
+        };
+        let Some((i, bytes)) = tuples.first() else {
+            self.report("addresses", Some(id), "tuple 1 is missing".to_string());
+            return;
+        };
+        let Some(edges) = self.decode("addresses", (id, *i), bytes, |bytes| {
+            AddressTokensTuple::deserialize_ref(bytes)
+                .edges()
+                .iter()
+                .map(|edge| edge.into_inner())
+                .collect::<Vec<_>>()
+        }) else {
+            return;
+        };
+        // prefixes of strings may repeat, while the order of keys of tokens is
+        // checked with their leaves
+        if !edges.is_sorted_by(|(l, _), (r, _)| l <= r)
+            || key.is_some_and(|key| edges.last().map(|&(last, _)| last) != Some(key))
+        {
+            self.report(
+                "addresses",
+                Some(id),
+                "node of the address tree has edges out of order".to_string(),
+            );
+        }
+        for (key, child) in edges {
+            self.address_tokens(depth - 1, child, Some(key), leaves, visited);
+        }
+    }
+
+    /// Checks that the positions of a block hold a list for each posting.
+    fn block_positions(
+        &mut self,
+        n: usize,
+        (page, i): Location,
+        wptr_positions: Location,
+        postings: usize,
+        chunks: &[(bool, Vec<u8>)],
+        offsets: &HashMap<Location, usize>,
+    ) {
+        if !self.positions {
+            if wptr_positions != (0, 0) {
+                let message = format!("segment {n} has a block with positions in tuple {i}");
+                self.report("blocks", Some(page), message);
+            }
+            return;
+        }
+        let mut bytes = Vec::new();
+        let mut ended = false;
+        if let Some(&offset) = offsets.get(&wptr_positions) {
+            for (last, chunk) in chunks[offset..].iter() {
+                bytes.extend_from_slice(chunk);
+                if *last {
+                    ended = true;
+                    break;
+                }
+            }
+        }
+        if !ended || !matches_positions(&bytes, postings) {
+            let message =
+                format!("segment {n} has a block whose positions do not match in tuple {i}");
+            self.report("positions", Some(page), message);
+        }
+    }
+
+    fn vectors(&mut self, ptr_vectors: u32, mut payloads: Option<&mut Vec<[u16; 3]>>) {
+        // a document is a `_2` tuple, followed by `_3`, `_4` and `_1` tuples
+        // and ended by a `_0` tuple
+        let tape = self.tape("vectors", ptr_vectors);
+        let mut state = None::<Pending>;
+        for (location, bytes) in tape.tuples.iter() {
+            let Some(tuple) =
+                self.decode(
+                    "vectors",
+                    *location,
+                    bytes,
+                    |bytes| match VectorTuple::deserialize_ref(bytes) {
+                        VectorTupleReader::_0(vector_tuple) => VectorTuple::_0 {
+                            deleted: vector_tuple.deleted(),
+                            payload: vector_tuple.payload(),
+                            elements: vector_tuple.elements().to_vec(),
+                        },
+                        VectorTupleReader::_1(vector_tuple) => VectorTuple::_1 {
+                            elements: vector_tuple.elements().to_vec(),
+                        },
+                        VectorTupleReader::_2(vector_tuple) => VectorTuple::_2 {
+                            fieldnorm: vector_tuple.fieldnorm(),
+                        },
+                        VectorTupleReader::_3(vector_tuple) => VectorTuple::_3 {
+                            positions: vector_tuple.positions().to_vec(),
+                        },
+                        VectorTupleReader::_4(vector_tuple) => VectorTuple::_4 {
+                            terms: vector_tuple.terms().to_vec(),
+                        },
+                    },
+                )
+            else {
+                state = None;
+                continue;
+            };
+            if let VectorTuple::_2 { fieldnorm } = tuple {
+                // a document that is not ended is left by a failed insertion
+                state = Some(Pending {
+                    fieldnorm,
+                    elements: Vec::new(),
+                    positions: Vec::new(),
+                    terms: Vec::new(),
+                });
+                continue;
+            }
+            let Some(pending) = state.as_mut() else {
+                let message = format!("tuple {} does not belong to a document", location.1);
+                self.report("vectors", Some(location.0), message);
+                continue;
+            };
+            match tuple {
+                VectorTuple::_0 {
+                    deleted,
+                    payload,
+                    elements,
+                } => {
+                    pending.elements.extend(elements);
+                    if let Some(pending) = state.take() {
+                        self.vector(location.0, pending);
+                    }
+                    if !bool::from(deleted)
+                        && let Some(payloads) = payloads.as_deref_mut()
+                    {
+                        payloads.push(payload);
+                    }
+                }
+                VectorTuple::_1 { elements } => pending.elements.extend(elements),
+                VectorTuple::_2 { .. } => unreachable!(),
+                VectorTuple::_3 { positions } => pending.positions.extend(positions),
+                VectorTuple::_4 { terms } => pending.terms.extend(terms),
+            }
+        }
+    }
+
+    fn vector(&mut self, page: u32, pending: Pending) {
+        let Some(document) = Document::checked_new(pending.elements) else {
+            let message = "document has terms out of order or without frequencies".to_string();
+            self.report("vectors", Some(page), message);
+            return;
+        };
+        if pending.fieldnorm != length_to_fieldnorm(document.length()) {
+            let message = "document has a length that does not match its terms".to_string();
+            self.report("vectors", Some(page), message);
+        }
+        if self.positions && !matches_positions(&pending.positions, document.len())
+            || !self.positions && !pending.positions.is_empty()
+        {
+            let message = "document has positions that do not match its terms".to_string();
+            self.report("vectors", Some(page), message);
+        }
+        if let Some(terms) = self.terms.as_ref() {
+            let decoded = catch_unwind(AssertUnwindSafe(|| {
+                let mut dictionary = Dictionary::new();
+                let _ =
+                    crate::dictionary::decode(document.as_slice(), &pending.terms, &mut dictionary);
+                dictionary
+            }));
+            let collision = decoded.as_ref().ok().and_then(|dictionary| {
+                dictionary.iter().find_map(|(key, term)| {
+                    let existing = terms.get(&key)?;
+                    (existing.as_slice() != term).then(|| (lossy(existing), lossy(term)))
+                })
+            });
+            if decoded.is_err() {
+                let message = "document has strings of terms that are corrupted".to_string();
+                self.report("vectors", Some(page), message);
+            } else if let Some((a, b)) = collision {
+                let message = format!("hash collision between terms {a:?} and {b:?}");
+                self.report("vectors", Some(page), message);
+            }
+        } else if !pending.terms.is_empty() {
+            let message =
+                "document has strings of terms but the index has no dictionary".to_string();
+            self.report("vectors", Some(page), message);
+        }
+    }
+}
+
+/// Returns whether the bytes hold exactly `n` lists of positions. Unlike
+/// `compression::decompress_positions`, it never allocates for a corrupted
+/// length.
+fn matches_positions(mut bytes: &[u8], n: usize) -> bool {
+    fn get(bytes: &mut &[u8]) -> Option<u32> {
+        let mut x = 0_u32;
+        for shift in (0..32).step_by(7) {
+            let (&byte, rest) = bytes.split_first()?;
+            *bytes = rest;
+            x |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Some(x);
+            }
+        }
+        None
+    }
+    for _ in 0..n {
+        let Some(len) = get(&mut bytes) else {
+            return false;
+        };
+        for _ in 0..len {
+            if get(&mut bytes).is_none() {
+                return false;
+            }
+        }
+    }
+    bytes.is_empty()
+}
+
+fn lossy(term: &[u8]) -> String {
+    String::from_utf8_lossy(term).into_owned()
+}
+
+#[test]
+fn positions_matched() {
+    let mut bytes = Vec::new();
+    compression::compress_positions(&[1, 5, 9], &mut bytes);
+    compression::compress_positions(&[300], &mut bytes);
+    assert!(matches_positions(&bytes, 2));
+    assert!(!matches_positions(&bytes, 1));
+    assert!(!matches_positions(&bytes, 3));
+    assert!(!matches_positions(&bytes[..bytes.len() - 1], 2));
+    assert!(!matches_positions(&[0xff; 8], 1));
+}
+
+#[cfg(test)]
+fn corrupted(
+    f: impl FnOnce(&crate::testing::MemoryRelation),
+) -> Vec<(&'static str, Option<u32>, String)> {
+    use crate::testing::*;
+    use crate::types::Bm25IndexOptions;
+    let index = build(
+        Bm25IndexOptions::default(),
+        &[
+            document(&[("postgres", 1), ("index", 2)]),
+            document(&[("postgres", 2), ("vector", 1)]),
+            document(&[("vector", 3)]),
+        ],
+    );
+    assert!(verify(&index, None).is_empty());
+    f(&index);
+    verify(&index, None)
+        .into_iter()
+        .map(|problem| (problem.region, problem.page, problem.message))
+        .collect()
+}
+
+#[test]
+fn verify_undeserializable() {
+    let problems = corrupted(|index| {
+        index.corrupt(0, |page| page.get_mut(1).unwrap()[..8].fill(0));
+    });
+    assert_eq!(
+        problems,
+        [("meta", Some(0), "tuple 1 is corrupted".to_string())]
+    );
+}
+
+#[test]
+fn verify_cycle() {
+    let mut ptr_documents = 0;
+    let problems = corrupted(|index| {
+        ptr_documents = crate::testing::segments(index)[0].ptr_documents;
+        index.corrupt(ptr_documents, |page| {
+            page.get_opaque_mut().next = ptr_documents;
+        });
+    });
+    assert_eq!(
+        problems,
+        [(
+            "documents",
+            Some(ptr_documents),
+            "page is already walked as a page of documents".to_string()
+        )]
+    );
+}
+
+#[test]
+fn verify_summary() {
+    let mut ptr_summaries = 0;
+    let problems = corrupted(|index| {
+        ptr_summaries = crate::testing::segments(index)[0].ptr_summaries;
+        index.corrupt(ptr_summaries, |page| {
+            let bytes = page.get_mut(1).unwrap();
+            let summary_tuple = SummaryTuple::deserialize_ref(bytes);
+            let forged = SummaryTuple {
+                min_document_id: summary_tuple.min_document_id(),
+                max_document_id: summary_tuple.max_document_id(),
+                number_of_documents: summary_tuple.number_of_documents(),
+                wand_fieldnorm: summary_tuple.wand_fieldnorm(),
+                wand_term_frequency: summary_tuple.wand_term_frequency() + 1,
+                wptr_block: summary_tuple.wptr_block(),
+            };
+            bytes.copy_from_slice(&forged.serialize());
+        });
+    });
+    assert_eq!(
+        problems,
+        [(
+            "summaries",
+            Some(ptr_summaries),
+            "segment 0 has a summary whose upper bound does not match its block".to_string()
+        )]
+    );
+}
+
+#[test]
+fn verify_deleted_documents() {
+    let problems = corrupted(|index| {
+        let meta_guard = index.read(0);
+        let meta_tuple = MetaTuple::deserialize_ref(meta_guard.get(1).unwrap());
+        let jump_guard = index.read(meta_tuple.ptr_jump());
+        let jump_tuple = JumpTuple::deserialize_ref(jump_guard.get(1).unwrap());
+        let ptr_segments = jump_tuple.ptr_segments();
+        drop((jump_guard, meta_guard));
+        index.corrupt(ptr_segments, |page| {
+            let bytes = page.get_mut(1).unwrap();
+            *SegmentTuple::deserialize_mut(bytes).number_of_deleted_documents() += 1;
+        });
+    });
+    assert_eq!(
+        problems,
+        [(
+            "documents",
+            None,
+            "segment 0 has 0 deleted documents but records 1".to_string()
+        )]
+    );
+}
@@ -76,11 +76,22 @@ pub trait RelationWriteTypes: Relation {
 
 pub trait RelationWrite: RelationWriteTypes {
     fn write(&self, id: u32) -> Self::WriteGuard<'_>;
+    fn try_write(&self, id: u32) -> Option<Self::WriteGuard<'_>>;
     fn alloc(&self, opaque: <Self::Page as Page>::Opaque) -> Self::WriteGuard<'_>;
     fn free(&self, guard: Self::WriteGuard<'_>);
     fn vacuum(&self);
 }
 
+pub trait RelationLength: Relation {
+    #[must_use]
+    fn len(&self) -> u32;
+    #[must_use]
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 pub trait RelationPrefetch: Relation {
     fn prefetch(&self, id: u32);
 }
@@ -0,0 +1,277 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::datatype::memory_tsquery::TsQueryOutput;
+use crate::datatype::memory_tsvector::TsVectorOutput;
+use crate::datatype::tsquery::cast_tsquery_to_phrases;
+use crate::datatype::tsvector::{DEFAULT_WEIGHTS, cast_tsvector_to_query};
+use bm25::WIDTH;
+use bm25::vector::{Query, intern};
+use pgrx::WhoAllocated;
+use pgrx::heap_tuple::PgHeapTuple;
+use pgrx::pg_sys::Oid;
+use std::collections::HashMap;
+use std::num::NonZero;
+
+/// Fields of the composite type `bm25query`.
+pub struct Bm25Query {
+    pub vector: TsVectorOutput,
+    pub index: Oid,
+    pub must: Option<TsVectorOutput>,
+    pub must_not: Option<TsVectorOutput>,
+    pub minimum_should_match: Option<String>,
+    pub weights: Option<Vec<Option<f32>>>,
+    pub phrase: Option<TsQueryOutput>,
+    pub slop: Option<i32>,
+    pub proximity: Option<f32>,
+    pub k1: Option<f32>,
+    pub b: Option<f32>,
+    pub prefixes: Option<Vec<Option<String>>>,
+    pub max_expansions: Option<i32>,
+    pub fuzziness: Option<i32>,
+    pub fuzzy_discount: Option<f32>,
+}
+
+impl Bm25Query {
+    pub fn from_tuple<A: WhoAllocated>(tuple: &PgHeapTuple<'_, A>) -> Self {
+        let vector = match tuple.get_by_index(NonZero::new(1).unwrap()) {
+            Ok(Some(s)) => s,
+            Ok(None) => pgrx::error!("bm25query contains a null vector"),
+            Err(_) => unreachable!(),
+        };
+        let index = match tuple.get_by_index(NonZero::new(2).unwrap()) {
+            Ok(Some(s)) => s,
+            Ok(None) => pgrx::error!("bm25query contains a null index"),
+            Err(_) => unreachable!(),
+        };
+        let must = match tuple.get_by_index(NonZero::new(3).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let must_not = match tuple.get_by_index(NonZero::new(4).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let minimum_should_match = match tuple.get_by_index(NonZero::new(5).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let weights =
+            match tuple.get_by_index::<pgrx::datum::Array<'_, f32>>(NonZero::new(6).unwrap()) {
+                Ok(s) => s.map(|s| s.iter().collect()),
+                Err(_) => unreachable!(),
+            };
+        let phrase = match tuple.get_by_index(NonZero::new(7).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let slop = match tuple.get_by_index(NonZero::new(8).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let proximity = match tuple.get_by_index(NonZero::new(9).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let k1 = match tuple.get_by_index(NonZero::new(10).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let b = match tuple.get_by_index(NonZero::new(11).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let prefixes =
+            match tuple.get_by_index::<pgrx::datum::Array<'_, String>>(NonZero::new(12).unwrap()) {
+                Ok(s) => s.map(|s| s.iter().collect()),
+                Err(_) => unreachable!(),
+            };
+        let max_expansions = match tuple.get_by_index(NonZero::new(13).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let fuzziness = match tuple.get_by_index(NonZero::new(14).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        let fuzzy_discount = match tuple.get_by_index(NonZero::new(15).unwrap()) {
+            Ok(s) => s,
+            Err(_) => unreachable!(),
+        };
+        Self {
+            vector,
+            index,
+            must,
+            must_not,
+            minimum_should_match,
+            weights,
+            phrase,
+            slop,
+            proximity,
+            k1,
+            b,
+            prefixes,
+            max_expansions,
+            fuzziness,
+            fuzzy_discount,
+        }
+    }
+
+    /// Returns the lexemes of the query by their keys.
+    pub fn lexemes(&self, seed: &[u8; 32]) -> HashMap<[u8; WIDTH], Vec<u8>> {
+        let mut result = HashMap::new();
+        for vector in std::iter::once(&self.vector).chain(self.must.as_ref()) {
+            for (lexeme, _) in vector.as_borrowed().iter() {
+                result.insert(intern(seed, lexeme), lexeme.to_vec());
+            }
+        }
+        if let Some(phrase) = self.phrase.as_ref() {
+            for lexeme in phrase.as_borrowed().lexemes() {
+                result.insert(intern(seed, lexeme), lexeme.to_vec());
+            }
+        }
+        result
+    }
+}
+
+/// The number of terms a prefix or a fuzzy term is expanded to by default.
+const DEFAULT_MAX_EXPANSIONS: usize = 50;
+
+/// The factor of the weight of a fuzzy match for each edit by default.
+const DEFAULT_FUZZY_DISCOUNT: f64 = 0.5;
+
+/// Casts a bm25query to a query, whose prefixes and fuzzy terms are expanded
+/// by `bm25::expand` later.
+pub fn cast_bm25query_to_query(seed: &[u8; 32], bm25query: &Bm25Query) -> Query {
+    let weights = if let Some(weights) = bm25query.weights.as_deref() {
+        let Ok(weights) = <[Option<f32>; 4]>::try_from(weights) else {
+            pgrx::error!("bm25query weights must have 4 elements");
+        };
+        weights.map(|weight| match weight {
+            Some(weight) if weight >= 0.0 && weight.is_finite() => weight as f64,
+            Some(_) => pgrx::error!("bm25query weights must be non-negative"),
+            None => pgrx::error!("bm25query weights must not contain nulls"),
+        })
+    } else {
+        DEFAULT_WEIGHTS
+    };
+    let keys = |vector: &Option<TsVectorOutput>| {
+        if let Some(vector) = vector {
+            cast_tsvector_to_query(seed, vector.as_borrowed(), &DEFAULT_WEIGHTS)
+                .as_slice()
+                .to_vec()
+        } else {
+            Vec::new()
+        }
+    };
+    let mut query = cast_tsvector_to_query(seed, bm25query.vector.as_borrowed(), &weights)
+        .with_clauses(keys(&bm25query.must), keys(&bm25query.must_not));
+    if let Some(phrase) = bm25query.phrase.as_ref() {
+        let slop = match bm25query.slop {
+            Some(slop) if slop >= 0 => slop as u32,
+            Some(_) => pgrx::error!("bm25query slop must be non-negative"),
+            None => 0,
+        };
+        query = query.with_phrases(cast_tsquery_to_phrases(seed, phrase.as_borrowed()), slop);
+    }
+    if let Some(proximity) = bm25query.proximity {
+        if !(proximity >= 0.0 && proximity.is_finite()) {
+            pgrx::error!("bm25query proximity must be non-negative");
+        }
+        query = query.with_proximity(proximity as f64);
+    }
+    let k1 = bm25query.k1.map(|k1| {
+        if !(0.0..=3.0).contains(&k1) {
+            pgrx::error!("bm25query k1 must be between 0 and 3");
+        }
+        k1 as f64
+    });
+    let b = bm25query.b.map(|b| {
+        if !(0.0..=1.0).contains(&b) {
+            pgrx::error!("bm25query b must be between 0 and 1");
+        }
+        b as f64
+    });
+    query = query.with_parameters(k1, b);
+    let max_expansions = match bm25query.max_expansions {
+        Some(max_expansions) if max_expansions > 0 => max_expansions as usize,
+        Some(_) => pgrx::error!("bm25query max_expansions must be positive"),
+        None => DEFAULT_MAX_EXPANSIONS,
+    };
+    if let Some(prefixes) = bm25query.prefixes.as_deref() {
+        let prefixes = prefixes
+            .iter()
+            .map(|prefix| match prefix {
+                Some(prefix) if !prefix.is_empty() => prefix.as_bytes().to_vec(),
+                Some(_) => pgrx::error!("bm25query prefixes must not be empty"),
+                None => pgrx::error!("bm25query prefixes must not contain nulls"),
+            })
+            .collect();
+        query = query.with_prefixes(prefixes, max_expansions);
+    }
+    if let Some(fuzziness) = bm25query.fuzziness {
+        if !(0..=2).contains(&fuzziness) {
+            pgrx::error!("bm25query fuzziness must be between 0 and 2");
+        }
+        let fuzzy_discount = bm25query
+            .fuzzy_discount
+            .map_or(DEFAULT_FUZZY_DISCOUNT, |x| {
+                if !(x > 0.0 && x <= 1.0) {
+                    pgrx::error!("bm25query fuzzy_discount must be between 0 and 1");
+                }
+                x as f64
+            });
+        let fuzzy = bm25query
+            .vector
+            .as_borrowed()
+            .iter()
+            .map(|(lexeme, _)| lexeme.to_vec())
+            .collect();
+        query = query.with_fuzziness(fuzzy, fuzziness as u8, fuzzy_discount);
+    }
+    if let Some(minimum_should_match) = bm25query.minimum_should_match.as_deref() {
+        let should = query.len() - query.must().len();
+        let Some(minimum_should_match) = parse_minimum_should_match(minimum_should_match, should)
+        else {
+            pgrx::error!("invalid minimum_should_match: {minimum_should_match:?}");
+        };
+        query.with_minimum_should_match(minimum_should_match)
+    } else {
+        query
+    }
+}
+
+/// Parses `N`, `-N`, `N%` or `-N%`, where a negative value is the number of
+/// optional terms that may be missing and a percentage is rounded down.
+fn parse_minimum_should_match(s: &str, should: usize) -> Option<usize> {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let value = if let Some(s) = s.strip_suffix('%') {
+        let percentage = s.trim().parse::<u32>().ok()?;
+        if percentage > 100 {
+            return None;
+        }
+        should * percentage as usize / 100
+    } else {
+        s.parse::<u32>().ok()? as usize
+    };
+    if negative {
+        Some(should.saturating_sub(value))
+    } else {
+        Some(value)
+    }
+}
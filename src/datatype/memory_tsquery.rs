@@ -0,0 +1,153 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::datatype::tsquery::TsQueryBorrowed;
+use pgrx::datum::{FromDatum, IntoDatum};
+use pgrx::pg_sys::{Datum, Oid};
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+#[repr(C)]
+pub struct TsQueryHeader {
+    varlena: u32,
+    size: i32,
+    data: [u8; 0],
+}
+
+impl TsQueryHeader {
+    unsafe fn as_borrowed<'a>(this: NonNull<Self>) -> TsQueryBorrowed<'a> {
+        unsafe {
+            let this = this.as_ptr();
+            let size = (cfg_select! {
+                target_endian = "little" => {
+                    ((*this).varlena >> 2) & 0x3FFFFFFF
+                }
+                target_endian = "big" => {
+                    (*this).varlena & 0x3FFFFFFF
+                }
+            } as usize)
+                .strict_sub(size_of::<u32>())
+                .strict_sub(size_of::<i32>());
+            let len = (*this).size as usize;
+            let data = std::slice::from_raw_parts((*this).data.as_ptr(), size);
+            TsQueryBorrowed::new(len, data)
+        }
+    }
+}
+
+pub struct TsQueryInput<'a>(NonNull<TsQueryHeader>, PhantomData<&'a ()>, bool);
+
+impl TsQueryInput<'_> {
+    unsafe fn from_ptr(p: NonNull<TsQueryHeader>) -> Self {
+        let q = unsafe {
+            NonNull::new(pgrx::pg_sys::pg_detoast_datum(p.cast().as_ptr()).cast()).unwrap()
+        };
+        TsQueryInput(q, PhantomData, p != q)
+    }
+    pub fn as_borrowed(&self) -> TsQueryBorrowed<'_> {
+        unsafe { TsQueryHeader::as_borrowed(self.0) }
+    }
+}
+
+impl Drop for TsQueryInput<'_> {
+    fn drop(&mut self) {
+        if self.2 {
+            unsafe {
+                pgrx::pg_sys::pfree(self.0.as_ptr().cast());
+            }
+        }
+    }
+}
+
+pub struct TsQueryOutput(NonNull<TsQueryHeader>);
+
+impl TsQueryOutput {
+    unsafe fn from_ptr(p: NonNull<TsQueryHeader>) -> Self {
+        let q = unsafe {
+            NonNull::new(pgrx::pg_sys::pg_detoast_datum_copy(p.as_ptr().cast()).cast()).unwrap()
+        };
+        Self(q)
+    }
+    pub fn as_borrowed(&self) -> TsQueryBorrowed<'_> {
+        unsafe { TsQueryHeader::as_borrowed(self.0) }
+    }
+    fn into_raw(self) -> *mut TsQueryHeader {
+        let result = self.0.as_ptr();
+        std::mem::forget(self);
+        result
+    }
+}
+
+impl Drop for TsQueryOutput {
+    fn drop(&mut self) {
+        unsafe {
+            pgrx::pg_sys::pfree(self.0.as_ptr().cast());
+        }
+    }
+}
+
+// FromDatum
+
+impl FromDatum for TsQueryInput<'_> {
+    unsafe fn from_polymorphic_datum(datum: Datum, is_null: bool, _typoid: Oid) -> Option<Self> {
+        if is_null {
+            None
+        } else {
+            let ptr = NonNull::new(datum.cast_mut_ptr()).unwrap();
+            unsafe { Some(Self::from_ptr(ptr)) }
+        }
+    }
+}
+
+impl FromDatum for TsQueryOutput {
+    unsafe fn from_polymorphic_datum(datum: Datum, is_null: bool, _typoid: Oid) -> Option<Self> {
+        if is_null {
+            None
+        } else {
+            let ptr = NonNull::new(datum.cast_mut_ptr()).unwrap();
+            unsafe { Some(Self::from_ptr(ptr)) }
+        }
+    }
+}
+
+// IntoDatum
+
+impl IntoDatum for TsQueryOutput {
+    fn into_datum(self) -> Option<Datum> {
+        Some(Datum::from(self.into_raw()))
+    }
+
+    fn type_oid() -> Oid {
+        Oid::INVALID
+    }
+
+    fn is_compatible_with(_: Oid) -> bool {
+        true
+    }
+}
+
+// UnboxDatum
+
+unsafe impl pgrx::datum::UnboxDatum for TsQueryOutput {
+    type As<'src> = TsQueryOutput;
+    #[inline]
+    unsafe fn unbox<'src>(datum: pgrx::datum::Datum<'src>) -> Self::As<'src>
+    where
+        Self: 'src,
+    {
+        let datum = datum.sans_lifetime();
+        let ptr = NonNull::new(datum.cast_mut_ptr()).unwrap();
+        unsafe { Self::from_ptr(ptr) }
+    }
+}
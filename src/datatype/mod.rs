@@ -12,5 +12,8 @@
 //
 // Copyright (c) 2025-2026 TensorChord Inc.
 
+pub mod bm25query;
+pub mod memory_tsquery;
 pub mod memory_tsvector;
+pub mod tsquery;
 pub mod tsvector;
@@ -0,0 +1,203 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use bm25::WIDTH;
+use bm25::vector::{Expression, Phrase, intern};
+
+const QI_VAL: u8 = 1;
+const QI_OPR: u8 = 2;
+const QI_VALSTOP: u8 = 3;
+
+const OP_NOT: u8 = 1;
+const OP_AND: u8 = 2;
+const OP_OR: u8 = 3;
+const OP_PHRASE: u8 = 4;
+
+// `sizeof(QueryItem)`
+const ITEM: usize = 12;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TsQueryBorrowed<'a> {
+    len: usize,
+    data: &'a [u8],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TsQueryItem<'a> {
+    Operand {
+        lexeme: &'a [u8],
+        weight: u8,
+        prefix: bool,
+    },
+    Stopword,
+    Not,
+    And,
+    Or,
+    Phrase {
+        distance: u16,
+    },
+}
+
+impl<'a> TsQueryBorrowed<'a> {
+    #[inline(always)]
+    pub fn new(len: usize, data: &'a [u8]) -> Self {
+        Self { len, data }
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the lexemes of all operands.
+    pub fn lexemes(&self) -> impl Iterator<Item = &'a [u8]> {
+        let this = *self;
+        (0..self.len).filter_map(move |i| match this.get(i) {
+            (TsQueryItem::Operand { lexeme, .. }, _) => Some(lexeme),
+            _ => None,
+        })
+    }
+
+    /// Returns the item and, for operators, the offset of its left operand.
+    /// The right operand, or the only operand of `Not`, follows the operator.
+    pub fn get(&self, i: usize) -> (TsQueryItem<'a>, usize) {
+        assert!(i < self.len, "invalid tsquery");
+        let item = &self.data[i * ITEM..][..ITEM];
+        match item[0] {
+            QI_VAL => {
+                let word = u32::from_ne_bytes(item[8..12].try_into().unwrap());
+                let (len, pos) = cfg_select! {
+                    target_endian = "little" => {
+                        (word & ((1 << 12) - 1), word >> 12)
+                    }
+                    target_endian = "big" => {
+                        (word >> 20, word & ((1 << 20) - 1))
+                    }
+                };
+                let operands = &self.data[self.len * ITEM..];
+                let lexeme = &operands[pos as usize..][..len as usize];
+                let operand = TsQueryItem::Operand {
+                    lexeme,
+                    weight: item[1],
+                    prefix: item[2] != 0,
+                };
+                (operand, 0)
+            }
+            QI_VALSTOP => (TsQueryItem::Stopword, 0),
+            QI_OPR => {
+                let left = u32::from_ne_bytes(item[4..8].try_into().unwrap()) as usize;
+                let operator = match item[1] {
+                    OP_NOT => TsQueryItem::Not,
+                    OP_AND => TsQueryItem::And,
+                    OP_OR => TsQueryItem::Or,
+                    OP_PHRASE => TsQueryItem::Phrase {
+                        distance: i16::from_ne_bytes(item[2..4].try_into().unwrap()) as u16,
+                    },
+                    _ => panic!("invalid tsquery"),
+                };
+                (operator, left)
+            }
+            _ => panic!("invalid tsquery"),
+        }
+    }
+}
+
+pub fn cast_tsquery_to_expression(seed: &[u8; 32], tsquery: TsQueryBorrowed<'_>) -> Expression {
+    // An empty tsquery matches nothing.
+    if tsquery.is_empty() {
+        return Expression::Constant(Some(false));
+    }
+    // Operands restricted by weights and phrases are checked against positions,
+    // which are not stored, so the index only excludes rows that cannot match.
+    fn inexact(x: Expression) -> Expression {
+        Expression::And(Box::new(x), Box::new(Expression::Constant(None)))
+    }
+    fn positive(x: &Expression) -> bool {
+        match x {
+            Expression::Constant(_) | Expression::Token(_) => true,
+            Expression::Not(_) => false,
+            Expression::And(l, r) | Expression::Or(l, r) => positive(l) && positive(r),
+        }
+    }
+    fn visit(seed: &[u8; 32], tsquery: TsQueryBorrowed<'_>, i: usize) -> Expression {
+        match tsquery.get(i) {
+            (TsQueryItem::Operand { prefix: true, .. }, _) => Expression::Constant(None),
+            (TsQueryItem::Operand { lexeme, weight, .. }, _) => {
+                let token = Expression::Token(intern(seed, lexeme));
+                if weight != 0 { inexact(token) } else { token }
+            }
+            (TsQueryItem::Stopword, _) => Expression::Constant(None),
+            (TsQueryItem::Not, _) => Expression::Not(Box::new(visit(seed, tsquery, i + 1))),
+            (TsQueryItem::And, left) => Expression::And(
+                Box::new(visit(seed, tsquery, i + left)),
+                Box::new(visit(seed, tsquery, i + 1)),
+            ),
+            (TsQueryItem::Or, left) => Expression::Or(
+                Box::new(visit(seed, tsquery, i + left)),
+                Box::new(visit(seed, tsquery, i + 1)),
+            ),
+            (TsQueryItem::Phrase { .. }, left) => {
+                // negations inside a phrase refer to positions, not documents
+                let [l, r] = [i + left, i + 1].map(|j| {
+                    let x = visit(seed, tsquery, j);
+                    if positive(&x) {
+                        x
+                    } else {
+                        Expression::Constant(None)
+                    }
+                });
+                inexact(Expression::And(Box::new(l), Box::new(r)))
+            }
+        }
+    }
+    visit(seed, tsquery, 0)
+}
+
+/// Casts a conjunction of phrases to phrases, where a single lexeme is a
+/// phrase of one term.
+pub fn cast_tsquery_to_phrases(seed: &[u8; 32], tsquery: TsQueryBorrowed<'_>) -> Vec<Phrase> {
+    fn chain(seed: &[u8; 32], tsquery: TsQueryBorrowed<'_>, i: usize) -> Vec<([u8; WIDTH], u32)> {
+        match tsquery.get(i) {
+            (
+                TsQueryItem::Operand {
+                    lexeme,
+                    weight: 0,
+                    prefix: false,
+                },
+                _,
+            ) => vec![(intern(seed, lexeme), 0)],
+            (TsQueryItem::Phrase { distance }, left) => {
+                let mut result = chain(seed, tsquery, i + left);
+                let shift = result[result.len() - 1].1 + distance as u32;
+                let right = chain(seed, tsquery, i + 1);
+                result.extend(right.into_iter().map(|(key, offset)| (key, offset + shift)));
+                result
+            }
+            _ => pgrx::error!("phrase must be a conjunction of lexemes and phrase operators"),
+        }
+    }
+    fn visit(seed: &[u8; 32], tsquery: TsQueryBorrowed<'_>, i: usize, result: &mut Vec<Phrase>) {
+        if let (TsQueryItem::And, left) = tsquery.get(i) {
+            visit(seed, tsquery, i + left, result);
+            visit(seed, tsquery, i + 1, result);
+        } else {
+            result.push(Phrase::new(chain(seed, tsquery, i)));
+        }
+    }
+    let mut result = Vec::new();
+    if !tsquery.is_empty() {
+        visit(seed, tsquery, 0, &mut result);
+    }
+    result
+}
@@ -52,20 +52,32 @@ impl<'a> TsVectorBorrowed<'a> {
         Self { entries, bytes }
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (&[u8], Option<u16>)> {
+    /// Iterates over lexemes with their positions, each of which holds the
+    /// label in the highest 2 bits and the position in the lower 14 bits.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], Option<Vec<u16>>)> {
         self.entries.iter().map(|&entry| {
             let haspos = (entry >> 0) & ((1 << 1) - 1);
             let len = (entry >> 1) & ((1 << 11) - 1);
             let pos = (entry >> 12) & ((1 << 20) - 1);
             let string = &self.bytes[pos as usize..][..len as usize];
-            let count = if haspos != 0 {
+            let positions = if haspos != 0 {
                 let offset = (pos + len).next_multiple_of(2) as usize;
                 let (lo, hi) = (self.bytes[offset], self.bytes[offset + 1]);
-                Some(u16::from_ne_bytes([lo, hi]))
+                let count = u16::from_ne_bytes([lo, hi]) as usize;
+                let positions = (0..count)
+                    .map(|i| {
+                        let (lo, hi) = (
+                            self.bytes[offset + 2 + 2 * i],
+                            self.bytes[offset + 3 + 2 * i],
+                        );
+                        u16::from_ne_bytes([lo, hi])
+                    })
+                    .collect();
+                Some(positions)
             } else {
                 None
             };
-            (string, count)
+            (string, positions)
         })
     }
 }
@@ -81,46 +93,106 @@ impl TsVectorBorrowed<'_> {
     }
 }
 
-pub fn cast_tsvector_to_document(seed: &[u8; 32], tsvector: TsVectorBorrowed<'_>) -> Document {
+/// Casts a tsvector to a document, where the term frequency is the sum of
+/// positions under each label multiplied by the multiplier of the label, see
+/// `Bm25IndexOptions::multipliers`.
+pub fn cast_tsvector_to_document(
+    seed: &[u8; 32],
+    tsvector: TsVectorBorrowed<'_>,
+    multipliers: &[u32; 4],
+) -> Document {
     let mut internal = Vec::new();
-    for (string, count) in tsvector.iter() {
+    for (string, positions) in tsvector.iter() {
         let key = intern(seed, string);
-        let value: u32 = count.expect("tsvector must have positions").into();
-        internal.push(Element { key, value });
+        let positions = positions.expect("tsvector must have positions");
+        let value = labels(&positions)
+            .into_iter()
+            .zip(multipliers)
+            .map(|(count, &multiplier)| Saturating(count) * Saturating(multiplier))
+            .sum::<Saturating<u32>>()
+            .0;
+        let positions = positions.iter().map(|&x| (x & 0x3fff) as u32).collect();
+        internal.push((Element { key, value }, positions, string));
     }
-    internal.sort_unstable_by(|Element { key: l, .. }, Element { key: r, .. }| Ord::cmp(l, r));
+    internal.sort_unstable_by(|(l, _, _), (r, _, _)| Ord::cmp(&l.key, &r.key));
     dedup(&mut internal);
+    let mut terms = Vec::with_capacity(internal.len());
+    let (internal, positions) = internal
+        .into_iter()
+        .map(|(element, positions, string)| {
+            terms.push(string.to_vec());
+            (element, positions)
+        })
+        .unzip();
     Document::new(internal)
+        .with_positions(positions)
+        .with_terms(terms)
 }
 
-pub fn cast_tsvector_to_query(seed: &[u8; 32], tsvector: TsVectorBorrowed<'_>) -> Query {
+/// Weights of labels `D`, `C`, `B` and `A`, in the same ratio as the defaults
+/// of `ts_rank`.
+pub const DEFAULT_WEIGHTS: [f64; 4] = [1.0, 2.0, 4.0, 10.0];
+
+pub fn cast_tsvector_to_query(
+    seed: &[u8; 32],
+    tsvector: TsVectorBorrowed<'_>,
+    weights: &[f64; 4],
+) -> Query {
     let mut internal = Vec::new();
-    for (string, _) in tsvector.iter() {
+    for (string, positions) in tsvector.iter() {
         let key = intern(seed, string);
-        internal.push(key);
+        let labels = positions.map_or([1, 0, 0, 0], |positions| labels(&positions));
+        let weight = (0..4).map(|i| labels[i] as f64 * weights[i]).sum::<f64>();
+        internal.push((key, weight));
+    }
+    internal.sort_unstable_by(|(l, _), (r, _)| Ord::cmp(l, r));
+    let mut weights = Vec::<f64>::with_capacity(internal.len());
+    let mut keys = Vec::with_capacity(internal.len());
+    for (key, weight) in internal {
+        if keys.last() == Some(&key) {
+            *weights.last_mut().unwrap() += weight;
+        } else {
+            keys.push(key);
+            weights.push(weight);
+        }
+    }
+    Query::new(keys).with_weights(weights)
+}
+
+fn labels(positions: &[u16]) -> [u32; 4] {
+    let mut labels = [0_u32; 4];
+    for position in positions {
+        labels[(position >> 14) as usize] += 1;
     }
-    internal.sort_unstable();
-    internal.dedup();
-    Query::new(internal)
+    labels
 }
 
-fn dedup(internal: &mut Vec<Element>) {
+fn dedup(internal: &mut Vec<(Element, Vec<u32>, &[u8])>) {
     let n = internal.len();
     let (mut i, mut j) = (0_usize, 0_usize);
     while i < n {
         let mut k = i + 1;
-        while k < n && internal[k].key == internal[i].key {
+        while k < n && internal[k].0.key == internal[i].0.key {
             k += 1;
         }
         let value = internal[i..k]
             .iter()
-            .map(|&Element { value, .. }| Saturating(value))
+            .map(|&(Element { value, .. }, _, _)| Saturating(value))
             .sum::<Saturating<u32>>()
             .0;
-        internal[j] = Element {
-            key: internal[i].key,
-            value,
-        };
+        let mut positions = internal[i..k]
+            .iter_mut()
+            .flat_map(|(_, positions, _)| std::mem::take(positions))
+            .collect::<Vec<_>>();
+        positions.sort_unstable();
+        internal[j] = (
+            Element {
+                key: internal[i].0.key,
+                value,
+            },
+            positions,
+            internal[i].2,
+        );
         (i, j) = (k, j + 1);
     }
     internal.truncate(j);
@@ -0,0 +1,210 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::index::gucs::{bm25_maintenance_max_workers, bm25_maintenance_naptime};
+use crate::index::storage::PostgresRelation;
+use crate::index::temp::{tempdir, tempfile};
+use pgrx::bgworkers::{
+    BackgroundWorker, BackgroundWorkerBuilder, BackgroundWorkerStatus, BgWorkerStartTime,
+    DynamicBackgroundWorker, SignalWakeFlags,
+};
+use pgrx::pg_sys::Oid;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+pub fn init() {
+    if bm25_maintenance_max_workers() == 0 {
+        return;
+    }
+    BackgroundWorkerBuilder::new("vchord_bm25 maintenance launcher")
+        .set_library("vchord_bm25")
+        .set_function("vchord_bm25_maintenance_launcher")
+        .enable_spi_access()
+        .set_start_time(BgWorkerStartTime::RecoveryFinished)
+        .set_restart_time(Some(Duration::from_secs(10)))
+        .load();
+}
+
+#[pgrx::pg_guard]
+#[unsafe(export_name = "vchord_bm25_maintenance_launcher")]
+extern "C-unwind" fn maintenance_launcher(_arg: pgrx::pg_sys::Datum) {
+    BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGHUP | SignalWakeFlags::SIGTERM);
+    BackgroundWorker::connect_worker_to_spi(None, None);
+    let mut queue = VecDeque::<Oid>::new();
+    let mut jobs = Vec::<(Oid, DynamicBackgroundWorker)>::new();
+    loop {
+        jobs.retain(|(_, job)| {
+            matches!(
+                job.pid(),
+                Ok(_) | Err(BackgroundWorkerStatus::NotYetStarted)
+            )
+        });
+        if queue.is_empty() {
+            queue.extend(BackgroundWorker::transaction(databases));
+        }
+        while jobs.len() < bm25_maintenance_max_workers() as usize {
+            let Some(database) = queue.pop_front() else {
+                break;
+            };
+            if jobs.iter().any(|(x, _)| *x == database) {
+                continue;
+            }
+            let builder = BackgroundWorkerBuilder::new("vchord_bm25 maintenance worker")
+                .set_library("vchord_bm25")
+                .set_function("vchord_bm25_maintenance_worker")
+                .enable_spi_access()
+                .set_argument(Some(database.into()));
+            if let Ok(job) = builder.load_dynamic() {
+                jobs.push((database, job));
+            } else {
+                // no free background worker slots, retry after naptime
+                queue.push_front(database);
+                break;
+            }
+        }
+        let naptime = Duration::from_secs(bm25_maintenance_naptime() as u64);
+        if !BackgroundWorker::wait_latch(Some(naptime)) {
+            break;
+        }
+        if BackgroundWorker::sighup_received() {
+            unsafe {
+                pgrx::pg_sys::ProcessConfigFile(pgrx::pg_sys::GucContext::PGC_SIGHUP);
+            }
+        }
+    }
+}
+
+#[pgrx::pg_guard]
+#[unsafe(export_name = "vchord_bm25_maintenance_worker")]
+extern "C-unwind" fn maintenance_worker(arg: pgrx::pg_sys::Datum) {
+    BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGHUP | SignalWakeFlags::SIGTERM);
+    let database = Oid::from(arg.value() as u32);
+    BackgroundWorker::connect_worker_to_spi_by_oid(Some(database), None);
+    // the launcher is not connected to any database, so it cannot see where
+    // the extension is installed
+    if !BackgroundWorker::transaction(installed) {
+        return;
+    }
+    for index in BackgroundWorker::transaction(indexes) {
+        if BackgroundWorker::sigterm_received() {
+            break;
+        }
+        BackgroundWorker::transaction(move || optimize(index));
+    }
+}
+
+fn databases() -> Vec<Oid> {
+    use pgrx::pg_sys::{AccessShareLock, FormData_pg_database, LOCKMODE};
+    let mut result = Vec::new();
+    unsafe {
+        let relation = pgrx::pg_sys::table_open(
+            pgrx::pg_sys::DatabaseRelationId,
+            AccessShareLock as LOCKMODE,
+        );
+        let scan = pgrx::pg_sys::table_beginscan_catalog(relation, 0, std::ptr::null_mut());
+        loop {
+            let tuple =
+                pgrx::pg_sys::heap_getnext(scan, pgrx::pg_sys::ScanDirection::ForwardScanDirection);
+            if tuple.is_null() {
+                break;
+            }
+            let form = pgrx::pg_sys::heap_tuple_get_struct::<FormData_pg_database>(tuple);
+            if (*form).datallowconn && !(*form).datistemplate {
+                result.push((*form).oid);
+            }
+        }
+        pgrx::pg_sys::heap_endscan(scan);
+        pgrx::pg_sys::table_close(relation, AccessShareLock as LOCKMODE);
+    }
+    result
+}
+
+fn installed() -> bool {
+    unsafe { pgrx::pg_sys::get_extension_oid(c"vchord_bm25".as_ptr(), true) != Oid::INVALID }
+}
+
+fn indexes() -> Vec<Oid> {
+    use pgrx::pg_sys::{AccessShareLock, FormData_pg_class, LOCKMODE};
+    let mut result = Vec::new();
+    unsafe {
+        let am = pgrx::pg_sys::get_index_am_oid(c"bm25".as_ptr(), true);
+        if am == Oid::INVALID {
+            return result;
+        }
+        let relation = pgrx::pg_sys::table_open(
+            pgrx::pg_sys::RelationRelationId,
+            AccessShareLock as LOCKMODE,
+        );
+        let scan = pgrx::pg_sys::table_beginscan_catalog(relation, 0, std::ptr::null_mut());
+        loop {
+            let tuple =
+                pgrx::pg_sys::heap_getnext(scan, pgrx::pg_sys::ScanDirection::ForwardScanDirection);
+            if tuple.is_null() {
+                break;
+            }
+            let form = pgrx::pg_sys::heap_tuple_get_struct::<FormData_pg_class>(tuple);
+            if (*form).relam == am
+                && (*form).relkind as u8 == pgrx::pg_sys::RELKIND_INDEX
+                && (*form).relpersistence as u8 != pgrx::pg_sys::RELPERSISTENCE_TEMP
+            {
+                result.push((*form).oid);
+            }
+        }
+        pgrx::pg_sys::heap_endscan(scan);
+        pgrx::pg_sys::table_close(relation, AccessShareLock as LOCKMODE);
+    }
+    result
+}
+
+fn optimize(oid: Oid) {
+    use pgrx::pg_sys::{LOCKMODE, NoLock, RowExclusiveLock};
+    unsafe {
+        if !pgrx::pg_sys::ConditionalLockRelationOid(oid, RowExclusiveLock as LOCKMODE) {
+            return;
+        }
+        let index_relation = pgrx::pg_sys::try_relation_open(oid, NoLock as LOCKMODE);
+        if index_relation.is_null() {
+            pgrx::pg_sys::UnlockRelationOid(oid, RowExclusiveLock as LOCKMODE);
+            return;
+        }
+        // the access method name may be taken by another extension
+        type FnPtr = unsafe extern "C-unwind" fn(
+            *mut pgrx::pg_sys::RelationData,
+            i32,
+            i32,
+        ) -> *mut pgrx::pg_sys::IndexScanDescData;
+        let is_bm25 = (*index_relation)
+            .rd_indam
+            .as_ref()
+            .and_then(|indam| indam.ambeginscan)
+            .is_some_and(|ambeginscan| {
+                std::ptr::fn_addr_eq::<FnPtr, FnPtr>(
+                    ambeginscan,
+                    crate::index::bm25::am::ambeginscan,
+                )
+            });
+        if is_bm25 && (*(*index_relation).rd_index).indisvalid {
+            let index = PostgresRelation::new(index_relation);
+            let check = || {
+                pgrx::pg_sys::check_for_interrupts!();
+            };
+            let tempdir = tempdir();
+            let tempfile = tempfile();
+            if let Err(collision) = bm25::optimize(&index, check, tempdir.path(), tempfile.path()) {
+                pgrx::warning!("{collision}");
+            }
+        }
+        pgrx::pg_sys::relation_close(index_relation, RowExclusiveLock as LOCKMODE);
+    }
+}
@@ -309,8 +309,13 @@ pub unsafe extern "C-unwind" fn ambuild(
     };
     reporter.phase(BuildPhase::from_code(BuildPhaseCode::Writing));
     let index = unsafe { PostgresRelation::new(index_relation) };
-    let segment = bm25::io::readers(tempdir.path(), total);
-    bm25::build(bm25_options.index, &index, seed, segment);
+    let segment = bm25::io::readers(tempdir.path(), total, bm25_options.index.positions);
+    let dictionary = if bm25_options.index.dictionary {
+        bm25::io::terms(tempdir.path(), total)
+    } else {
+        bm25::Dictionary::new()
+    };
+    bm25::build(bm25_options.index, &index, seed, segment, dictionary);
     unsafe { pgrx::pgbox::PgBox::<pgrx::pg_sys::IndexBuildResult>::alloc0().into_pg() }
 }
 
@@ -700,9 +705,17 @@ unsafe fn build(
     sync_1: impl FnOnce(),
     sync_2: impl FnOnce(),
 ) {
+    let options = unsafe { options(index_relation) }.index;
+    let multipliers = options.multipliers();
     let order = sync_0();
     let mut records_writer = bm25::io::records_writer(path, order);
     let mut mappings_writer = bm25::io::mappings_writer(path, order);
+    let mut positions_writer = options
+        .positions
+        .then(|| bm25::io::positions_writer(path, order));
+    let mut terms_writer = options
+        .dictionary
+        .then(|| bm25::io::terms_writer(path, order));
 
     let traverser = unsafe { HeapTraverser::new(heap_relation, index_relation, index_info, scan) };
 
@@ -719,12 +732,18 @@ unsafe fn build(
                 break 'block None;
             }
             let vector = unsafe { TsVectorInput::from_datum(datum, false).unwrap() };
-            Some(cast_tsvector_to_document(&seed, vector.as_borrowed()))
+            Some(cast_tsvector_to_document(
+                &seed,
+                vector.as_borrowed(),
+                &multipliers,
+            ))
         };
         if let Some(document) = document {
             bm25::io::write(
                 &mut records_writer,
                 &mut mappings_writer,
+                positions_writer.as_mut(),
+                terms_writer.as_mut(),
                 &document,
                 ctid_to_key(ctid),
             );
@@ -738,8 +757,16 @@ unsafe fn build(
 
     records_writer.flush();
     mappings_writer.flush();
+    if let Some(positions_writer) = positions_writer.as_mut() {
+        positions_writer.flush();
+    }
+    if let Some(terms_writer) = terms_writer.as_mut() {
+        terms_writer.flush();
+    }
     drop(records_writer);
     drop(mappings_writer);
+    drop(positions_writer);
+    drop(terms_writer);
     bm25::io::locally_merge(path, order);
 
     sync_2();
@@ -36,6 +36,7 @@ pub unsafe extern "C-unwind" fn amvacuumcleanup(
     };
     let tempdir = tempdir();
     let tempfile = tempfile();
-    bm25::maintain(&index, check, tempdir.path(), tempfile.path());
+    bm25::maintain(&index, check, tempdir.path(), tempfile.path())
+        .unwrap_or_else(|collision| pgrx::error!("{collision}"));
     stats
 }
@@ -22,6 +22,7 @@ use crate::index::fetcher::*;
 use crate::index::gucs;
 use crate::index::scanners::SearchBuilder;
 use crate::index::storage::PostgresRelation;
+use crate::index::temp::{tempdir, tempfile};
 use pgrx::datum::Internal;
 use pgrx::pg_sys::Datum;
 use std::cell::LazyCell;
@@ -36,6 +37,7 @@ pub struct Reloption {
     options: i32,
     limit: i32,
     prefilter: bool,
+    seal_threshold: i32,
 }
 
 impl Reloption {
@@ -68,6 +70,14 @@ impl Reloption {
             (*this).prefilter
         }
     }
+    pub unsafe fn seal_threshold(this: *const Self, default: i32) -> i32 {
+        unsafe {
+            if this.is_null() {
+                return default;
+            }
+            (*this).seal_threshold
+        }
+    }
 }
 
 const TABLE: &[pgrx::pg_sys::relopt_parse_elt] = &[
@@ -92,6 +102,13 @@ const TABLE: &[pgrx::pg_sys::relopt_parse_elt] = &[
         #[cfg(feature = "pg18")]
         isset_offset: 0,
     },
+    pgrx::pg_sys::relopt_parse_elt {
+        optname: c"seal_threshold".as_ptr(),
+        opttype: pgrx::pg_sys::relopt_type::RELOPT_TYPE_INT,
+        offset: std::mem::offset_of!(Reloption, seal_threshold) as i32,
+        #[cfg(feature = "pg18")]
+        isset_offset: 0,
+    },
 ];
 
 static RELOPT_KIND: OnceLock<pgrx::pg_sys::relopt_kind::Type> = OnceLock::new();
@@ -125,6 +142,15 @@ pub fn init() {
                 false,
                 pgrx::pg_sys::AccessExclusiveLock as pgrx::pg_sys::LOCKMODE,
             );
+            pgrx::pg_sys::add_int_reloption(
+                kind as _,
+                c"seal_threshold".as_ptr(),
+                c"Number of pages of unsealed documents, counting the last page even if it is empty, that triggers sealing on insertion, 0 to disable".as_ptr(),
+                0,
+                0,
+                i32::MAX,
+                pgrx::pg_sys::AccessExclusiveLock as pgrx::pg_sys::LOCKMODE,
+            );
         }
         kind
     });
@@ -249,6 +275,27 @@ pub unsafe extern "C-unwind" fn amcostestimate(
         if !(*index_opt_info).hypothetical {
             // todo(usamoi)
         }
+        // a scan without orderbys reads the postings of its clauses, so it is
+        // charged like other index scans for the fraction of the index it reads
+        if (*path).indexorderbys.is_null() {
+            let mut random_page_cost = 0.0;
+            pgrx::pg_sys::get_tablespace_page_costs(
+                (*index_opt_info).reltablespace,
+                &mut random_page_cost,
+                std::ptr::null_mut(),
+            );
+            let pages = ((*index_opt_info).pages as f64 * selectivity)
+                .ceil()
+                .max(1.0);
+            let tuples = ((*index_opt_info).tuples * selectivity).max(1.0);
+            *index_startup_cost = 0.0;
+            *index_total_cost =
+                pages * random_page_cost + tuples * pgrx::pg_sys::cpu_index_tuple_cost;
+            *index_selectivity = selectivity;
+            *index_correlation = 0.0;
+            *index_pages = pages;
+            return;
+        }
         *index_startup_cost = 0.0;
         *index_total_cost = 0.0;
         *index_selectivity = selectivity;
@@ -270,6 +317,7 @@ pub unsafe extern "C-unwind" fn aminsert(
 ) -> bool {
     let index = unsafe { PostgresRelation::new(index_relation) };
     let seed = bm25::seed::seed(&index);
+    let multipliers = bm25::seed::multipliers(&index);
     let value = unsafe { (!is_null.add(0).read()).then_some(values.add(0).read()) };
     let ctid = unsafe { heap_tid.read() };
     let document = 'block: {
@@ -281,10 +329,32 @@ pub unsafe extern "C-unwind" fn aminsert(
             break 'block None;
         }
         let vector = unsafe { TsVectorInput::from_datum(datum, false).unwrap() };
-        Some(cast_tsvector_to_document(&seed, vector.as_borrowed()))
+        Some(cast_tsvector_to_document(
+            &seed,
+            vector.as_borrowed(),
+            &multipliers,
+        ))
     };
     if let Some(document) = document {
-        bm25::insert(&index, &document, ctid_to_key(ctid));
+        let pages = bm25::insert(&index, &document, ctid_to_key(ctid))
+            .unwrap_or_else(|collision| pgrx::error!("{collision}"));
+        let seal_threshold =
+            unsafe { Reloption::seal_threshold((*index_relation).rd_options as _, 0) };
+        if seal_threshold != 0 && pages >= seal_threshold as u32 {
+            let check = || {
+                pgrx::pg_sys::check_for_interrupts!();
+            };
+            let tempdir = tempdir();
+            let tempfile = tempfile();
+            bm25::seal(
+                &index,
+                seal_threshold as u32,
+                check,
+                tempdir.path(),
+                tempfile.path(),
+            )
+            .unwrap_or_else(|collision| pgrx::error!("{collision}"));
+        }
     }
     false
 }
@@ -333,6 +403,8 @@ pub unsafe extern "C-unwind" fn ambeginscan(
     let scan = unsafe { pgrx::pg_sys::RelationGetIndexScan(index_relation, n_keys, n_orderbys) };
     let scanner: Scanner = Scanner {
         hack: None,
+        limit: None,
+        recheck: false,
         scanning: LazyCell::new(Box::new(|| Box::new(std::iter::empty()))),
     };
     unsafe {
@@ -364,10 +436,7 @@ pub unsafe extern "C-unwind" fn amrescan(
         let scanner = &mut *(*scan).opaque.cast::<Scanner>();
         scanner.scanning = LazyCell::new(Box::new(|| Box::new(std::iter::empty())));
         let index = PostgresRelation::new((*scan).indexRelation);
-        let options = SearchOptions {
-            limit: gucs::bm25_limit((*scan).indexRelation),
-            prefilter: gucs::bm25_prefilter((*scan).indexRelation),
-        };
+        let limit = scanner.limit;
         let fetcher = {
             let hack = scanner.hack;
             LazyCell::new(move || {
@@ -398,7 +467,19 @@ pub unsafe extern "C-unwind" fn amrescan(
                 let is_null = ((*data).sk_flags & pgrx::pg_sys::SK_ISNULL as i32) != 0;
                 builder.add((*data).sk_strategy, (!is_null).then_some(value));
             }
-            LazyCell::new(Box::new(move || builder.build(index, options, fetcher)))
+            scanner.recheck = builder.recheck();
+            LazyCell::new(Box::new(move || {
+                let options = SearchOptions {
+                    limit: gucs::bm25_limit((*scan).indexRelation),
+                    // evaluated by the Limit node before it fetches the first row
+                    bound: limit.and_then(|limit| {
+                        let limit = limit.as_ref();
+                        (!limit.noCount).then(|| limit.offset.saturating_add(limit.count) as u64)
+                    }),
+                    prefilter: gucs::bm25_prefilter((*scan).indexRelation),
+                };
+                builder.build(index, options, fetcher)
+            }))
         };
     }
 }
@@ -423,7 +504,7 @@ pub unsafe extern "C-unwind" fn amgettuple(
     if let Some((_, key)) = scanner.scanning.deref_mut().next() {
         unsafe {
             (*scan).xs_heaptid = key_to_ctid(key);
-            (*scan).xs_recheck = false;
+            (*scan).xs_recheck = scanner.recheck;
             (*scan).xs_recheckorderby = false;
         }
         true
@@ -442,5 +523,7 @@ type Iter = Box<dyn Iterator<Item = (f64, [u16; 3])>>;
 
 pub struct Scanner {
     pub hack: Option<NonNull<pgrx::pg_sys::IndexScanState>>,
+    pub limit: Option<NonNull<pgrx::pg_sys::LimitState>>,
+    recheck: bool,
     scanning: LazyCell<Iter, Box<dyn FnOnce() -> Iter>>,
 }
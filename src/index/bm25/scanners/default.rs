@@ -12,23 +12,35 @@
 //
 // Copyright (c) 2025-2026 TensorChord Inc.
 
-use crate::datatype::memory_tsvector::TsVectorOutput;
-use crate::datatype::tsvector::cast_tsvector_to_query;
+use crate::datatype::bm25query::{Bm25Query, cast_bm25query_to_query};
+use crate::datatype::memory_tsquery::TsQueryInput;
+use crate::datatype::tsquery::cast_tsquery_to_expression;
 use crate::index::bm25::scanners::SearchOptions;
+use crate::index::bm25::scanners::fallback::Fallback;
 use crate::index::fetcher::*;
 use crate::index::scanners::SearchBuilder;
 use always_equal::AlwaysEqual;
-use bm25::vector::Query;
+use bm25::vector::{Expression, Query};
 use index::relation::{Page, RelationId, RelationRead};
 use pgrx::heap_tuple::PgHeapTuple;
 use pgrx::pg_sys::Oid;
 use std::cmp::Reverse;
 use std::num::NonZero;
 
+const DEFAULT_LIMIT: u64 = 100;
+
 pub struct DefaultBuilder {
     oid: Oid,
     seed: [u8; 32],
     orderbys: Vec<Option<Query>>,
+    expression: Option<Expression>,
+}
+
+impl DefaultBuilder {
+    /// Returns whether rows must be rechecked against `@@` by PostgreSQL.
+    pub fn recheck(&self) -> bool {
+        self.expression.as_ref().is_some_and(|x| !x.is_exact())
+    }
 }
 
 impl SearchBuilder for DefaultBuilder {
@@ -49,12 +61,14 @@ impl SearchBuilder for DefaultBuilder {
             oid,
             seed,
             orderbys: Vec::new(),
+            expression: None,
         }
     }
 
     unsafe fn add(&mut self, strategy: u16, value: Option<pgrx::pg_sys::Datum>) {
         match strategy {
-            1 => {
+            // `<&&>` is a decreasing function of the score for a given query
+            1 | 3 => {
                 let document = 'block: {
                     use pgrx::datum::FromDatum;
                     let Some(datum) = value else {
@@ -66,26 +80,33 @@ impl SearchBuilder for DefaultBuilder {
                     let rhs = unsafe {
                         PgHeapTuple::<'_, pgrx::AllocatedByRust>::from_datum(datum, false).unwrap()
                     };
-                    let vector: TsVectorOutput = match rhs.get_by_index(NonZero::new(1).unwrap()) {
-                        Ok(Some(s)) => s,
-                        Ok(None) => pgrx::error!("bm25query contains a null vector"),
-                        Err(_) => unreachable!(),
-                    };
-                    let index: Oid = match rhs.get_by_index(NonZero::new(2).unwrap()) {
-                        Ok(Some(s)) => s,
-                        Ok(None) => pgrx::error!("bm25query contains a null index"),
-                        Err(_) => unreachable!(),
-                    };
+                    let bm25query = Bm25Query::from_tuple(&rhs);
+                    let index = bm25query.index;
                     if index != self.oid {
                         pgrx::error!(
                             "expected index {index}, but got index {} for scan",
                             self.oid
                         );
                     }
-                    Some(cast_tsvector_to_query(&self.seed, vector.as_borrowed()))
+                    Some(cast_bm25query_to_query(&self.seed, &bm25query))
                 };
                 self.orderbys.push(document);
             }
+            2 => {
+                use pgrx::datum::FromDatum;
+                // `@@` is strict, so a null tsquery matches nothing
+                let expression = match value {
+                    Some(datum) if !datum.is_null() => {
+                        let tsquery = unsafe { TsQueryInput::from_datum(datum, false).unwrap() };
+                        cast_tsquery_to_expression(&self.seed, tsquery.as_borrowed())
+                    }
+                    _ => Expression::Constant(Some(false)),
+                };
+                self.expression = Some(match self.expression.take() {
+                    Some(x) => Expression::And(Box::new(x), Box::new(expression)),
+                    None => expression,
+                });
+            }
             _ => unreachable!(),
         }
     }
@@ -97,7 +118,7 @@ impl SearchBuilder for DefaultBuilder {
         mut fetcher: impl Fetcher + 'b,
     ) -> Box<dyn Iterator<Item = (f64, [u16; 3])> + 'b>
     where
-        R: RelationRead,
+        R: RelationRead + 'b,
         R::Page: Page<Opaque = bm25::Opaque>,
     {
         let mut vector = None;
@@ -108,28 +129,46 @@ impl SearchBuilder for DefaultBuilder {
                 pgrx::error!("vector search with multiple vectors is not supported");
             }
         }
-        let Some(vector) = vector else {
-            return Box::new(std::iter::empty()) as Box<dyn Iterator<Item = (f64, [u16; 3])>>;
+        let expression = self.expression;
+        let Some(vector) = vector.map(|vector| bm25::expand(&index, vector)) else {
+            let Some(expression) = expression else {
+                return Box::new(std::iter::empty()) as Box<dyn Iterator<Item = (f64, [u16; 3])>>;
+            };
+            let result = bm25::matching(&index, &expression);
+            return Box::new(result.into_iter().map(|pointer| (0.0, pointer)));
         };
-        let Some(limit) = NonZero::new(options.limit as usize) else {
-            pgrx::error!("number of needed rows is set to 0");
+        if vector.positional() && !bm25::seed::positions(&index) {
+            pgrx::error!("phrase and proximity queries require an index with positions = true");
+        }
+        // `bm25.limit` caps the bound inferred from the query, and the rest
+        // of rows are produced by the fallback
+        let limit = match (options.bound, options.limit) {
+            (Some(bound), 0) => bound,
+            (Some(bound), limit) => bound.min(limit as u64),
+            (None, 0) => DEFAULT_LIMIT,
+            (None, limit) => limit as u64,
         };
-        let result = if !options.prefilter {
-            bm25::search(&index, limit, &vector, |_| true)
-        } else {
-            bm25::search(&index, limit, &vector, |pointer| {
-                let Some(mut tuple) = fetcher.fetch(pointer) else {
-                    return false;
-                };
-                if !tuple.filter() {
-                    return false;
-                }
-                true
-            })
+        let limit = NonZero::new(limit.clamp(1, usize::MAX as u64) as usize).unwrap();
+        let prefilter = options.prefilter;
+        let search = move |k| {
+            let result = if !prefilter {
+                bm25::search(&index, k, &vector, expression.as_ref(), |_| true)
+            } else {
+                bm25::search(&index, k, &vector, expression.as_ref(), |pointer| {
+                    let Some(mut tuple) = fetcher.fetch(pointer) else {
+                        return false;
+                    };
+                    if !tuple.filter() {
+                        return false;
+                    }
+                    true
+                })
+            };
+            result
+                .into_iter()
+                .map(|(Reverse(score), AlwaysEqual(pointer))| (score.to_f64(), pointer))
+                .collect()
         };
-        let iter = result
-            .into_iter()
-            .map(move |(Reverse(score), AlwaysEqual(pointer))| (score.to_f64(), pointer));
-        Box::new(iter)
+        Box::new(Fallback::new(limit, search))
     }
 }
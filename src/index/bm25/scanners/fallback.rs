@@ -0,0 +1,71 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use std::collections::HashSet;
+use std::num::NonZero;
+
+// Top-k search can only answer a bounded number of rows. Once a batch is
+// consumed, search again with a doubled k and skip rows that have been
+// returned, so that the scan is unbounded as long as PostgreSQL keeps asking.
+pub struct Fallback<F> {
+    k: NonZero<usize>,
+    search: F,
+    batch: std::vec::IntoIter<(f64, [u16; 3])>,
+    returned: HashSet<[u16; 3]>,
+    exhausted: bool,
+}
+
+impl<F> Fallback<F>
+where
+    F: FnMut(NonZero<usize>) -> Vec<(f64, [u16; 3])>,
+{
+    pub fn new(k: NonZero<usize>, mut search: F) -> Self {
+        let batch = search(k);
+        let exhausted = batch.len() < k.get();
+        Self {
+            k,
+            search,
+            batch: batch.into_iter(),
+            returned: HashSet::new(),
+            exhausted,
+        }
+    }
+}
+
+impl<F> Iterator for Fallback<F>
+where
+    F: FnMut(NonZero<usize>) -> Vec<(f64, [u16; 3])>,
+{
+    type Item = (f64, [u16; 3]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((score, pointer)) = self.batch.next() {
+                if self.returned.insert(pointer) {
+                    return Some((score, pointer));
+                }
+                continue;
+            }
+            if self.exhausted {
+                return None;
+            }
+            self.k = self
+                .k
+                .saturating_mul(NonZero::<usize>::MIN.saturating_add(1));
+            let batch = (self.search)(self.k);
+            self.exhausted = batch.len() < self.k.get();
+            self.batch = batch.into_iter();
+        }
+    }
+}
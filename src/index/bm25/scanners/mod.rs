@@ -13,13 +13,13 @@
 // Copyright (c) 2025-2026 TensorChord Inc.
 
 mod default;
+mod fallback;
 
 pub use default::DefaultBuilder;
 
-// todo(usamoi): add a fallback scanner
-
 #[derive(Debug)]
 pub struct SearchOptions {
     pub limit: u32,
+    pub bound: Option<u64>,
     pub prefilter: bool,
 }
@@ -0,0 +1,421 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::datatype::bm25query::{Bm25Query, cast_bm25query_to_query};
+use crate::datatype::memory_tsvector::TsVectorInput;
+use crate::datatype::tsvector::{DEFAULT_WEIGHTS, cast_tsvector_to_query};
+use crate::index::fetcher::{Fetcher, HeapFetcher, ctid_to_key, key_to_ctid};
+use crate::index::operators::Index;
+use crate::index::storage::PostgresRelation;
+use crate::index::traverse::{HeapTraverser, Traverser, Tuple};
+use always_equal::AlwaysEqual;
+use bm25::WIDTH;
+use bm25::vector::{Query, intern};
+use pgrx::iter::TableIterator;
+use pgrx::pg_sys::{ItemPointerData, Oid};
+use pgrx::{default, name};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::num::NonZero;
+
+/// Fuses the top `k` rows of a bm25 query with another ranked list of rows,
+/// by reciprocal rank fusion or by a weighted sum of their scores, each list
+/// of which is min-max normalized so that its best is 1 and its worst is 0.
+#[pgrx::pg_extern(sql = "", stable, parallel_safe)]
+fn _bm25_fuse(
+    query: pgrx::composite_type!("bm25query"),
+    ids: Vec<Option<ItemPointerData>>,
+    scores: default!(Option<Vec<Option<f64>>>, "NULL"),
+    method: default!(&str, "'rrf'"),
+    k: default!(i32, 10),
+    rrf_k: default!(i32, 60),
+    weight: default!(f64, 0.5),
+    higher_is_better: default!(bool, true),
+) -> TableIterator<
+    'static,
+    (
+        name!(ctid, ItemPointerData),
+        name!(score, f64),
+        name!(bm25_rank, Option<i32>),
+        name!(other_rank, Option<i32>),
+    ),
+> {
+    let Some(k) = usize::try_from(k).ok().and_then(NonZero::new) else {
+        pgrx::error!("k must be positive");
+    };
+    if rrf_k < 0 {
+        pgrx::error!("rrf_k must be non-negative");
+    }
+    if !(0.0..=1.0).contains(&weight) {
+        pgrx::error!("weight must be between 0 and 1");
+    }
+    let ids = ids
+        .into_iter()
+        .map(|id| id.unwrap_or_else(|| pgrx::error!("ids must not contain nulls")))
+        .collect::<Vec<_>>();
+    let scores = scores.map(|scores| {
+        if scores.len() != ids.len() {
+            pgrx::error!("scores must have as many elements as ids");
+        }
+        scores
+            .into_iter()
+            .map(|score| match score {
+                Some(score) if score.is_finite() => score,
+                Some(_) => pgrx::error!("scores must be finite"),
+                None => pgrx::error!("scores must not contain nulls"),
+            })
+            .collect::<Vec<_>>()
+    });
+    let bm25query = Bm25Query::from_tuple(&query);
+    let relation = Index::open_bm25(bm25query.index);
+    let index = unsafe { PostgresRelation::new(relation.raw()) };
+    let seed = bm25::seed::seed(&index);
+    let query = bm25::expand(&index, cast_bm25query_to_query(&seed, &bm25query));
+    let results = search(&relation, &query, k);
+    let results = results
+        .into_iter()
+        .map(|(score, key, _)| (score, key))
+        .collect::<Vec<_>>();
+    // the first occurrence of a row decides its rank
+    let mut fused = HashMap::<[u16; 3], (f64, Option<i32>, Option<i32>)>::new();
+    match method {
+        "rrf" => {
+            let rrf = |rank: usize| 1.0 / (rrf_k as f64 + rank as f64);
+            for (i, &(_, key)) in results.iter().enumerate() {
+                let entry = fused.entry(key).or_default();
+                entry.0 += rrf(i + 1);
+                entry.1 = Some(i as i32 + 1);
+            }
+            for (i, &id) in ids.iter().enumerate() {
+                let entry = fused.entry(ctid_to_key(id)).or_default();
+                if entry.2.is_none() {
+                    entry.0 += rrf(i + 1);
+                    entry.2 = Some(i as i32 + 1);
+                }
+            }
+        }
+        "weighted" => {
+            let Some(scores) = scores else {
+                pgrx::error!("weighted fusion requires scores");
+            };
+            let bm25_scores = results.iter().map(|&(score, _)| score).collect::<Vec<_>>();
+            let bm25_scores = normalize(&bm25_scores, true);
+            for (i, (&(_, key), &score)) in results.iter().zip(bm25_scores.iter()).enumerate() {
+                let entry = fused.entry(key).or_default();
+                entry.0 += weight * score;
+                entry.1 = Some(i as i32 + 1);
+            }
+            let scores = normalize(&scores, higher_is_better);
+            for (i, (&id, &score)) in ids.iter().zip(scores.iter()).enumerate() {
+                let entry = fused.entry(ctid_to_key(id)).or_default();
+                if entry.2.is_none() {
+                    entry.0 += (1.0 - weight) * score;
+                    entry.2 = Some(i as i32 + 1);
+                }
+            }
+        }
+        _ => pgrx::error!("method must be rrf or weighted"),
+    }
+    let mut rows = fused.into_iter().collect::<Vec<_>>();
+    rows.sort_by(|(_, l), (_, r)| {
+        r.0.total_cmp(&l.0)
+            .then_with(|| l.1.unwrap_or(i32::MAX).cmp(&r.1.unwrap_or(i32::MAX)))
+            .then_with(|| l.2.unwrap_or(i32::MAX).cmp(&r.2.unwrap_or(i32::MAX)))
+    });
+    rows.truncate(k.get());
+    TableIterator::new(
+        rows.into_iter()
+            .map(|(key, (score, bm25_rank, other_rank))| {
+                (key_to_ctid(key), score, bm25_rank, other_rank)
+            }),
+    )
+}
+
+/// Scales scores to `[0, 1]`, where the best score is 1. If all scores are
+/// equal, they are all the best.
+fn normalize(scores: &[f64], higher_is_better: bool) -> Vec<f64> {
+    let min = scores.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    scores
+        .iter()
+        .map(|&score| {
+            if min == max {
+                1.0
+            } else if higher_is_better {
+                (score - min) / (max - min)
+            } else {
+                (max - score) / (max - min)
+            }
+        })
+        .collect()
+}
+
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_search(
+    index: Oid,
+    query: TsVectorInput,
+    k: i32,
+) -> TableIterator<
+    'static,
+    (
+        name!(ctid, ItemPointerData),
+        name!(score, f64),
+        name!(terms, Vec<String>),
+    ),
+> {
+    let Some(k) = usize::try_from(k).ok().and_then(NonZero::new) else {
+        pgrx::error!("k must be positive");
+    };
+    let relation = Index::open_bm25(index);
+    let index = unsafe { PostgresRelation::<bm25::Opaque>::new(relation.raw()) };
+    let seed = bm25::seed::seed(&index);
+    let mut lexemes = HashMap::new();
+    for (lexeme, _) in query.as_borrowed().iter() {
+        lexemes.insert(intern(&seed, lexeme), lexeme.to_vec());
+    }
+    let query = cast_tsvector_to_query(&seed, query.as_borrowed(), &DEFAULT_WEIGHTS);
+    let rows = search(&relation, &query, k)
+        .into_iter()
+        .map(|(score, key, terms)| {
+            let terms = terms
+                .iter()
+                .filter_map(|term| lexemes.get(term))
+                .map(|lexeme| String::from_utf8_lossy(lexeme).into_owned())
+                .collect::<Vec<_>>();
+            (key_to_ctid(key), score, terms)
+        })
+        .collect::<Vec<_>>();
+    TableIterator::new(rows)
+}
+
+/// Lists the terms of an index with their document frequencies. Terms longer
+/// than the width of keys are listed as `NULL` unless the index stores a
+/// dictionary.
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_vocabulary(
+    index: Oid,
+) -> TableIterator<'static, (name!(term, Option<String>), name!(document_frequency, i64))> {
+    let relation = Index::open_bm25(index);
+    let index = unsafe { PostgresRelation::<bm25::Opaque>::new(relation.raw()) };
+    let rows = bm25::vocabulary(&index)
+        .into_iter()
+        .map(|(_, term, document_frequency)| {
+            let term = term.map(|term| String::from_utf8_lossy(&term).into_owned());
+            (term, document_frequency as i64)
+        })
+        .collect::<Vec<_>>();
+    TableIterator::new(rows)
+}
+
+/// Returns the statistics of the documents of an index and the numbers of
+/// pages of its regions.
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_index_stats(
+    index: Oid,
+) -> TableIterator<
+    'static,
+    (
+        name!(number_of_segments, i64),
+        name!(number_of_documents, i64),
+        name!(number_of_deleted_documents, i64),
+        name!(number_of_unsealed_documents, i64),
+        name!(sum_of_document_lengths, f64),
+        name!(avgdl, f64),
+        name!(vectors_pages, i64),
+        name!(segments_pages, i64),
+        name!(terms_pages, i64),
+        name!(documents_pages, i64),
+        name!(tokens_pages, i64),
+        name!(summaries_pages, i64),
+        name!(blocks_pages, i64),
+        name!(positions_pages, i64),
+        name!(addresses_pages, i64),
+    ),
+> {
+    let relation = Index::open_bm25(index);
+    let index = unsafe { PostgresRelation::<bm25::Opaque>::new(relation.raw()) };
+    let stats = bm25::index_stats(&index);
+    let pages = stats.pages;
+    TableIterator::once((
+        stats.number_of_segments as i64,
+        stats.number_of_documents as i64,
+        stats.number_of_deleted_documents as i64,
+        stats.number_of_unsealed_documents as i64,
+        stats.sum_of_document_lengths,
+        stats.avgdl,
+        pages.vectors as i64,
+        pages.segments as i64,
+        pages.terms as i64,
+        pages.documents as i64,
+        pages.tokens as i64,
+        pages.summaries as i64,
+        pages.blocks as i64,
+        pages.positions as i64,
+        pages.addresses as i64,
+    ))
+}
+
+/// Returns the statistics of a lexeme in the segments of an index, which are
+/// what scoring uses.
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_term_stats(
+    index: Oid,
+    term: &str,
+) -> TableIterator<
+    'static,
+    (
+        name!(document_frequency, i64),
+        name!(sum_of_term_frequencies, f64),
+        name!(idf, f64),
+        name!(max_term_frequency, f64),
+        name!(number_of_blocks, i64),
+    ),
+> {
+    let relation = Index::open_bm25(index);
+    let index = unsafe { PostgresRelation::<bm25::Opaque>::new(relation.raw()) };
+    let seed = bm25::seed::seed(&index);
+    let stats = bm25::term_stats(&index, intern(&seed, term.as_bytes()));
+    TableIterator::once((
+        stats.document_frequency as i64,
+        stats.sum_of_term_frequencies,
+        stats.idf,
+        stats.max_term_frequency,
+        stats.number_of_blocks as i64,
+    ))
+}
+
+/// Checks the structure of an index, returning a row for each problem. With
+/// `heapallindexed`, it also checks that every row of the table is indexed.
+#[pgrx::pg_extern(sql = "", strict)]
+fn _bm25_verify(
+    index: Oid,
+    heapallindexed: bool,
+) -> TableIterator<
+    'static,
+    (
+        name!(region, String),
+        name!(page, Option<i64>),
+        name!(problem, String),
+    ),
+> {
+    use pgrx::pg_sys::ShareLock;
+    // insertions, vacuum and background merging take locks on the index that
+    // conflict with `ShareLock`, so it does not change while it is checked
+    let relation = Index::open_bm25_with(index, ShareLock as _);
+    let heaprelid = unsafe { pgrx::pg_sys::IndexGetRelation(index, false) };
+    let index = unsafe { PostgresRelation::<bm25::Opaque>::new(relation.raw()) };
+    let mut payloads = Vec::new();
+    let mut rows = bm25::verify(&index, heapallindexed.then_some(&mut payloads))
+        .into_iter()
+        .map(|problem| {
+            let page = problem.page.map(i64::from);
+            (problem.region.to_string(), page, problem.message)
+        })
+        .collect::<Vec<_>>();
+    if heapallindexed {
+        payloads.sort_unstable();
+        unsafe {
+            let heap_relation = pgrx::pg_sys::table_open(heaprelid, ShareLock as _);
+            let index_info = pgrx::pg_sys::BuildIndexInfo(relation.raw());
+            let traverser = HeapTraverser::new(
+                heap_relation,
+                relation.raw(),
+                index_info,
+                std::ptr::null_mut(),
+            );
+            traverser.traverse(false, |tuple: &mut dyn Tuple| {
+                let ctid = tuple.id();
+                let (values, is_nulls) = tuple.build();
+                if is_nulls.read() || values.read().is_null() {
+                    return;
+                }
+                if payloads.binary_search(&ctid_to_key(ctid)).is_err() {
+                    let (block, offset) = pgrx::itemptr::item_pointer_get_both(ctid);
+                    let problem = format!("row ({block},{offset}) is not indexed");
+                    rows.push(("heap".to_string(), None, problem));
+                }
+            });
+            pgrx::pg_sys::table_close(heap_relation, ShareLock as _);
+        }
+    }
+    TableIterator::new(rows)
+}
+
+/// Returns the top `k` rows of the table that are visible to the active
+/// snapshot, with their scores and the terms of the query they contain.
+fn search(
+    relation: &Index,
+    query: &Query,
+    k: NonZero<usize>,
+) -> Vec<(f64, [u16; 3], Vec<[u8; WIDTH]>)> {
+    let index = unsafe { PostgresRelation::new(relation.raw()) };
+    if query.positional() && !bm25::seed::positions(&index) {
+        pgrx::error!("phrase and proximity queries require an index with positions = true");
+    }
+    let table = Table::open(relation);
+    let mut fetcher = unsafe {
+        HeapFetcher::new(
+            relation.raw(),
+            table.raw,
+            pgrx::pg_sys::GetActiveSnapshot(),
+            table.heapfetch,
+            std::ptr::null_mut(),
+        )
+    };
+    bm25::search_with_terms(&index, k, query, None, |pointer| {
+        fetcher.fetch(pointer).is_some()
+    })
+    .into_iter()
+    .map(|(Reverse(score), AlwaysEqual((pointer, terms)))| (score.to_f64(), pointer, terms))
+    .collect()
+}
+
+struct Table {
+    raw: pgrx::pg_sys::Relation,
+    heapfetch: *mut pgrx::pg_sys::IndexFetchTableData,
+}
+
+impl Table {
+    fn open(index: &Index) -> Self {
+        unsafe {
+            use pgrx::pg_sys::ffi::pg_guard_ffi_boundary;
+            let indrelid = (*(*index.raw()).rd_index).indrelid;
+            let raw = pgrx::pg_sys::table_open(indrelid, pgrx::pg_sys::AccessShareLock as _);
+            let table_am = (*raw).rd_tableam;
+            if table_am.is_null() {
+                panic!("unknown heap access method");
+            }
+            let index_fetch_begin = (*table_am)
+                .index_fetch_begin
+                .expect("unsupported heap access method");
+            #[allow(ffi_unwind_calls, reason = "protected by pg_guard_ffi_boundary")]
+            let heapfetch = pg_guard_ffi_boundary(|| index_fetch_begin(raw));
+            Self { raw, heapfetch }
+        }
+    }
+}
+
+impl Drop for Table {
+    fn drop(&mut self) {
+        unsafe {
+            use pgrx::pg_sys::ffi::pg_guard_ffi_boundary;
+            let table_am = (*self.raw).rd_tableam;
+            if let Some(index_fetch_end) = (*table_am).index_fetch_end {
+                #[allow(ffi_unwind_calls, reason = "protected by pg_guard_ffi_boundary")]
+                pg_guard_ffi_boundary(|| index_fetch_end(self.heapfetch));
+            }
+            pgrx::pg_sys::table_close(self.raw, pgrx::pg_sys::AccessShareLock as _);
+        }
+    }
+}
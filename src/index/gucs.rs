@@ -25,6 +25,10 @@ static BM25_PREFILTER: GucSetting<bool> = GucSetting::<bool>::new(false);
 
 static mut BM25_PREFILTER_CONFIG: *mut pgrx::pg_sys::config_generic = core::ptr::null_mut();
 
+static BM25_MAINTENANCE_MAX_WORKERS: GucSetting<i32> = GucSetting::<i32>::new(0);
+
+static BM25_MAINTENANCE_NAPTIME: GucSetting<i32> = GucSetting::<i32>::new(60);
+
 pub fn init() {
     GucRegistry::define_bool_guc(
         c"bm25.enable_scan",
@@ -52,6 +56,26 @@ pub fn init() {
         GucContext::Userset,
         GucFlags::default(),
     );
+    GucRegistry::define_int_guc(
+        c"bm25.maintenance_max_workers",
+        c"Maximum number of concurrent maintenance workers of bm25, 0 to disable",
+        c"Maximum number of concurrent maintenance workers of bm25, 0 to disable",
+        &BM25_MAINTENANCE_MAX_WORKERS,
+        0,
+        64,
+        GucContext::Postmaster,
+        GucFlags::default(),
+    );
+    GucRegistry::define_int_guc(
+        c"bm25.maintenance_naptime",
+        c"Time to sleep between maintenance runs of bm25",
+        c"Time to sleep between maintenance runs of bm25",
+        &BM25_MAINTENANCE_NAPTIME,
+        1,
+        86400,
+        GucContext::Sighup,
+        GucFlags::UNIT_S,
+    );
     unsafe {
         #[cfg(feature = "pg14")]
         pgrx::pg_sys::EmitWarningsOnPlaceholders(c"bm25".as_ptr());
@@ -144,6 +168,14 @@ pub fn bm25_prefilter(index: pgrx::pg_sys::Relation) -> bool {
     }
 }
 
+pub fn bm25_maintenance_max_workers() -> u32 {
+    BM25_MAINTENANCE_MAX_WORKERS.get() as u32
+}
+
+pub fn bm25_maintenance_naptime() -> u32 {
+    BM25_MAINTENANCE_NAPTIME.get() as u32
+}
+
 #[allow(dead_code)]
 fn guc_name_compare(a: &CStr, b: &CStr) -> std::cmp::Ordering {
     let (a, b) = (a.to_bytes_with_nul(), b.to_bytes_with_nul());
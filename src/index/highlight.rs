@@ -0,0 +1,292 @@
+// This software is licensed under a dual license model:
+//
+// GNU Affero General Public License v3 (AGPLv3): You may use, modify, and
+// distribute this software under the terms of the AGPLv3.
+//
+// Elastic License v2 (ELv2): You may also use, modify, and distribute this
+// software under the Elastic License v2, which has specific restrictions.
+//
+// We welcome any commercial collaboration or support. For inquiries
+// regarding the licenses, please contact us at:
+// vectorchord-inquiry@tensorchord.ai
+//
+// Copyright (c) 2025-2026 TensorChord Inc.
+
+use crate::index::operators::{Index, Prepared};
+use crate::index::storage::PostgresRelation;
+use pgrx::pg_sys::Oid;
+use std::collections::HashMap;
+use std::ffi::CString;
+
+struct Options {
+    start_sel: String,
+    stop_sel: String,
+    max_words: usize,
+    max_fragments: usize,
+    fragment_delimiter: String,
+}
+
+/// Parses options in the format of `ts_headline`, like
+/// `StartSel=<b>, StopSel=</b>, MaxWords=35`.
+fn parse_options(s: &str) -> Options {
+    let mut options = Options {
+        start_sel: "<b>".to_string(),
+        stop_sel: "</b>".to_string(),
+        max_words: 35,
+        max_fragments: 1,
+        fragment_delimiter: " ... ".to_string(),
+    };
+    let mut chars = s.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=' && *c != ',') {
+            key.push(c);
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.next() != Some('=') {
+            pgrx::error!("invalid highlight options: {s:?}");
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') if chars.next_if_eq(&'"').is_none() => break,
+                    Some(c) => value.push(c),
+                    None => pgrx::error!("invalid highlight options: {s:?}"),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ',') {
+                value.push(c);
+            }
+        }
+        let positive = |value: &str| match value.parse::<usize>() {
+            Ok(value) if value > 0 => value,
+            _ => pgrx::error!("{key} must be a positive integer"),
+        };
+        match key.to_ascii_lowercase().as_str() {
+            "startsel" => options.start_sel = value,
+            "stopsel" => options.stop_sel = value,
+            "maxwords" => options.max_words = positive(&value),
+            "maxfragments" => options.max_fragments = positive(&value),
+            "fragmentdelimiter" => options.fragment_delimiter = value,
+            _ => pgrx::error!("unrecognized highlight option: {key:?}"),
+        }
+    }
+    options
+}
+
+/// Returns the best fragments of the document for the query, in which terms
+/// of the query are marked. Fragments are ranked by the sum of the idf of
+/// distinct terms in them.
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_highlight(
+    document: &str,
+    config: Oid,
+    _query: pgrx::composite_type!("bm25query"),
+    options: &str,
+    fcinfo: pgrx::pg_sys::FunctionCallInfo,
+) -> String {
+    let options = parse_options(options);
+    let prepared = unsafe { Prepared::get(fcinfo, 2) };
+    let relation = Index::open_bm25(prepared.index);
+    let index = unsafe { PostgresRelation::<bm25::Opaque>::new(relation.raw()) };
+    let query = &prepared.query;
+    let lexemes = &prepared.lexemes;
+    // terms in no segment do not contribute to scores, so they are not marked
+    let weights = query
+        .iter()
+        .zip(bm25::term_weights(&index, query))
+        .filter(|&(_, weight)| weight > 0.0)
+        .filter_map(|(key, weight)| Some((lexemes.get(key)?.clone(), weight)))
+        .collect::<HashMap<_, _>>();
+    let words = unsafe { parse(config, document, &weights) };
+    let positions = words
+        .iter()
+        .enumerate()
+        .filter(|(_, word)| !word.blank)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let n = positions.len();
+    let width = options.max_words;
+    let fragments = if n <= width {
+        vec![(0, words.len())]
+    } else {
+        let lexeme = |i: usize| words[positions[i]].lexeme.as_ref();
+        let weight = |lexeme| weights.get(lexeme).copied().unwrap_or(0.0);
+        let mut selected = Vec::<(usize, usize)>::new();
+        for _ in 0..options.max_fragments {
+            let mut best = None::<(f64, f64, usize)>;
+            // the window is slid by one word at a time, keeping the number of
+            // occurrences of each lexeme in it
+            let mut counts = HashMap::<&Vec<u8>, usize>::new();
+            let mut score = 0.0;
+            let mut sum_of_matches = 0;
+            let mut number_of_matches = 0;
+            for end in 1..=n {
+                if let Some(lexeme) = lexeme(end - 1) {
+                    let count = counts.entry(lexeme).or_default();
+                    if *count == 0 {
+                        score += weight(lexeme);
+                    }
+                    *count += 1;
+                    sum_of_matches += end - 1;
+                    number_of_matches += 1;
+                }
+                if end > width
+                    && let Some(lexeme) = lexeme(end - width - 1)
+                {
+                    let count = counts.get_mut(lexeme).expect("missing lexeme");
+                    *count -= 1;
+                    if *count == 0 {
+                        counts.remove(lexeme);
+                        score -= weight(lexeme);
+                    }
+                    sum_of_matches -= end - width - 1;
+                    number_of_matches -= 1;
+                }
+                if counts.is_empty() {
+                    score = 0.0;
+                }
+                if end < width {
+                    continue;
+                }
+                let start = end - width;
+                if selected.iter().any(|&(s, e)| start < e && s < end) {
+                    continue;
+                }
+                if counts.is_empty() {
+                    continue;
+                }
+                // prefer fragments where matches are in the middle
+                let center = sum_of_matches as f64 / number_of_matches as f64;
+                let offset = (center - (start + end - 1) as f64 / 2.0).abs();
+                if best.is_none_or(|(s, o, _)| score > s || (score == s && offset < o)) {
+                    best = Some((score, offset, start));
+                }
+            }
+            let Some((_, _, start)) = best else {
+                break;
+            };
+            selected.push((start, start + width));
+        }
+        if selected.is_empty() {
+            selected.push((0, width));
+        }
+        selected.sort_unstable();
+        selected
+            .into_iter()
+            .map(|(s, e)| (positions[s], positions[e - 1] + 1))
+            .collect()
+    };
+    let mut result = String::new();
+    for (i, &(start, end)) in fragments.iter().enumerate() {
+        if i != 0 {
+            result.push_str(&options.fragment_delimiter);
+        }
+        for word in words[start..end].iter() {
+            if word.lexeme.is_some() {
+                result.push_str(&options.start_sel);
+                result.push_str(&word.text);
+                result.push_str(&options.stop_sel);
+            } else {
+                result.push_str(&word.text);
+            }
+        }
+    }
+    result
+}
+
+const URL_T: u32 = 5;
+const NUMHWORD: u32 = 15;
+const ASCIIHWORD: u32 = 16;
+const HWORD: u32 = 17;
+
+struct Word {
+    text: String,
+    /// Whether the token is spaces or punctuation, which is not counted in
+    /// `MaxWords`.
+    blank: bool,
+    lexeme: Option<Vec<u8>>,
+}
+
+/// Splits the document into tokens with the parser of the configuration, and
+/// finds the lexemes that are produced by the dictionaries for each token.
+unsafe fn parse(config: Oid, document: &str, lexemes: &HashMap<Vec<u8>, f64>) -> Vec<Word> {
+    use pgrx::pg_sys::{HeadlineParsedText, HeadlineWordEntry, QueryItem, TSQueryData};
+    unsafe {
+        let tsquery = if lexemes.is_empty() {
+            let size = std::mem::offset_of!(TSQueryData, data);
+            let tsquery = pgrx::pg_sys::palloc0(size) as *mut TSQueryData;
+            pgrx::set_varsize_4b(tsquery.cast(), size as i32);
+            tsquery
+        } else {
+            let operands = lexemes
+                .keys()
+                .map(|lexeme| {
+                    let mut operand = String::from("'");
+                    for c in String::from_utf8_lossy(lexeme).chars() {
+                        if c == '\'' || c == '\\' {
+                            operand.push('\\');
+                        }
+                        operand.push(c);
+                    }
+                    operand.push('\'');
+                    operand
+                })
+                .collect::<Vec<_>>();
+            let input = CString::new(operands.join(" | ")).unwrap();
+            let datum = pgrx::direct_function_call::<pgrx::pg_sys::Datum>(
+                pgrx::pg_sys::tsqueryin,
+                &[pgrx::IntoDatum::into_datum(input.as_c_str())],
+            )
+            .unwrap();
+            datum.cast_mut_ptr::<TSQueryData>()
+        };
+        let mut prs = HeadlineParsedText {
+            lenwords: 32,
+            words: pgrx::pg_sys::palloc0(32 * size_of::<HeadlineWordEntry>()).cast(),
+            ..Default::default()
+        };
+        let mut buf = document.as_bytes().to_vec();
+        pgrx::pg_sys::hlparsetext(
+            config,
+            &mut prs,
+            tsquery,
+            buf.as_mut_ptr().cast(),
+            buf.len() as i32,
+        );
+        let operands = (tsquery as *const u8)
+            .add(std::mem::offset_of!(TSQueryData, data))
+            .add((*tsquery).size as usize * size_of::<QueryItem>());
+        let mut words = Vec::with_capacity(prs.curwords as usize);
+        for i in 0..prs.curwords as usize {
+            let word = &*prs.words.add(i);
+            // URLs and hyphenated words of the default parser are followed by
+            // their parts, like `prsd_headline`
+            if matches!(word.type_(), URL_T | NUMHWORD | ASCIIHWORD | HWORD) {
+                continue;
+            }
+            let text = std::slice::from_raw_parts(word.word as *const u8, word.len() as usize);
+            let text = String::from_utf8_lossy(text).into_owned();
+            let lexeme = word.item.as_ref().map(|item| {
+                std::slice::from_raw_parts(
+                    operands.add(item.distance() as usize),
+                    item.length() as usize,
+                )
+                .to_vec()
+            });
+            words.push(Word {
+                blank: !text.chars().any(char::is_alphanumeric),
+                text,
+                lexeme,
+            });
+        }
+        words
+    }
+}
@@ -54,6 +54,7 @@ unsafe extern "C-unwind" fn rewrite_plan_state(
 
                     let scanner = &mut *((*(*node).iss_ScanDesc).opaque as *mut Scanner);
                     scanner.hack = std::ptr::NonNull::new(node);
+                    scanner.limit = std::ptr::NonNull::new(context.cast());
 
                     if (*node).iss_NumRuntimeKeys == 0 || (*node).iss_RuntimeKeysReady {
                         pgrx::pg_sys::index_rescan(
@@ -67,7 +68,70 @@ unsafe extern "C-unwind" fn rewrite_plan_state(
                 }
             }
         }
-        pgrx::pg_sys::planstate_tree_walker(node, Some(rewrite_plan_state), context)
+        // A bound of an enclosing Limit node is passed down through nodes that
+        // neither reorder nor drop rows before them, like `ExecSetTupleBound`.
+        let limit = match (*node).type_ {
+            pgrx::pg_sys::NodeTag::T_LimitState => node.cast(),
+            pgrx::pg_sys::NodeTag::T_ResultState | pgrx::pg_sys::NodeTag::T_SubqueryScanState
+                if (*node).qual.is_null() =>
+            {
+                context
+            }
+            pgrx::pg_sys::NodeTag::T_AppendState | pgrx::pg_sys::NodeTag::T_MergeAppendState => {
+                context
+            }
+            _ => core::ptr::null_mut(),
+        };
+        if limit.is_null() {
+            return pgrx::pg_sys::planstate_tree_walker(
+                node,
+                Some(rewrite_plan_state),
+                core::ptr::null_mut(),
+            );
+        }
+        let children: &[*mut pgrx::pg_sys::PlanState] = match (*node).type_ {
+            pgrx::pg_sys::NodeTag::T_LimitState | pgrx::pg_sys::NodeTag::T_ResultState => {
+                std::slice::from_ref(&(*node).lefttree)
+            }
+            pgrx::pg_sys::NodeTag::T_SubqueryScanState => {
+                let node = node as *mut pgrx::pg_sys::SubqueryScanState;
+                std::slice::from_ref(&(*node).subplan)
+            }
+            pgrx::pg_sys::NodeTag::T_AppendState => {
+                let node = node as *mut pgrx::pg_sys::AppendState;
+                slice((*node).appendplans, (*node).as_nplans)
+            }
+            pgrx::pg_sys::NodeTag::T_MergeAppendState => {
+                let node = node as *mut pgrx::pg_sys::MergeAppendState;
+                slice((*node).mergeplans, (*node).ms_nplans)
+            }
+            _ => unreachable!(),
+        };
+        for &child in children {
+            if !child.is_null() {
+                rewrite_plan_state(child, limit);
+            }
+        }
+        // These nodes have no other children, so only subplans are left, which
+        // are not bounded.
+        for list in [(*node).initPlan, (*node).subPlan] {
+            if list.is_null() {
+                continue;
+            }
+            for cell in slice((*list).elements, (*list).length) {
+                let subplan = cell.ptr_value as *mut pgrx::pg_sys::SubPlanState;
+                rewrite_plan_state((*subplan).planstate, core::ptr::null_mut());
+            }
+        }
+        false
+    }
+}
+
+unsafe fn slice<'a, T>(ptr: *mut T, len: core::ffi::c_int) -> &'a [T] {
+    if ptr.is_null() || len <= 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(ptr, len as usize) }
     }
 }
 
@@ -12,9 +12,12 @@
 //
 // Copyright (c) 2025-2026 TensorChord Inc.
 
+mod bgworker;
 mod bm25;
 mod fetcher;
+mod functions;
 mod gucs;
+mod highlight;
 mod hook;
 mod operators;
 mod scanners;
@@ -26,4 +29,5 @@ pub fn init() {
     gucs::init();
     hook::init();
     bm25::am::init();
+    bgworker::init();
 }
@@ -12,61 +12,264 @@
 //
 // Copyright (c) 2025-2026 TensorChord Inc.
 
-use crate::datatype::memory_tsvector::{TsVectorInput, TsVectorOutput};
-use crate::datatype::tsvector::{cast_tsvector_to_document, cast_tsvector_to_query};
+use crate::datatype::bm25query::{Bm25Query, cast_bm25query_to_query};
+use crate::datatype::memory_tsvector::TsVectorInput;
+use crate::datatype::tsvector::cast_tsvector_to_document;
 use crate::index::storage::PostgresRelation;
+use bm25::WIDTH;
+use bm25::vector::Query;
+use pgrx::iter::TableIterator;
+use pgrx::name;
 use pgrx::pg_sys::Oid;
 use pgrx_catalog::{PgAm, PgClass, PgClassRelkind};
-use std::num::NonZero;
+use std::cell::OnceCell;
+use std::collections::HashMap;
 
-#[pgrx::pg_extern(stable, strict, parallel_safe)]
-pub fn _bm25_evaluate(lhs: TsVectorInput, rhs: pgrx::composite_type!("bm25query")) -> f64 {
-    let vector: TsVectorOutput = match rhs.get_by_index(NonZero::new(1).unwrap()) {
-        Ok(Some(s)) => s,
-        Ok(None) => pgrx::error!("bm25query contains a null vector"),
-        Err(_) => unreachable!(),
-    };
-    let index: Oid = match rhs.get_by_index(NonZero::new(2).unwrap()) {
-        Ok(Some(s)) => s,
-        Ok(None) => pgrx::error!("bm25query contains a null index"),
-        Err(_) => unreachable!(),
-    };
-    let pg_am = PgAm::search_amname(c"bm25").unwrap();
-    let Some(pg_am) = pg_am.get() else {
-        pgrx::error!("vchord_bm25 is not installed");
-    };
-    let pg_class = PgClass::search_reloid(index).unwrap();
-    let Some(pg_class) = pg_class.get() else {
-        pgrx::error!("the relation does not exist");
-    };
-    if pg_class.relkind() != PgClassRelkind::Index {
-        pgrx::error!("the relation {:?} is not an index", pg_class.relname());
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_evaluate(
+    lhs: TsVectorInput,
+    _rhs: pgrx::composite_type!("bm25query"),
+    fcinfo: pgrx::pg_sys::FunctionCallInfo,
+) -> f64 {
+    -unsafe { score(fcinfo, lhs) }
+}
+
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_evaluate_normalized(
+    lhs: TsVectorInput,
+    _rhs: pgrx::composite_type!("bm25query"),
+    fcinfo: pgrx::pg_sys::FunctionCallInfo,
+) -> f64 {
+    1.0 - unsafe { normalized_score(fcinfo, lhs) }
+}
+
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_score(
+    document: TsVectorInput,
+    _query: pgrx::composite_type!("bm25query"),
+    fcinfo: pgrx::pg_sys::FunctionCallInfo,
+) -> f64 {
+    unsafe { score(fcinfo, document) }
+}
+
+/// Returns the score divided by the maximum score that a document of the
+/// index can get for the query, clamped to 1 for documents that are not in
+/// the index, so that it is between 0 and 1.
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_normalized_score(
+    document: TsVectorInput,
+    _query: pgrx::composite_type!("bm25query"),
+    fcinfo: pgrx::pg_sys::FunctionCallInfo,
+) -> f64 {
+    unsafe { normalized_score(fcinfo, document) }
+}
+
+/// # Safety
+///
+/// The second argument of `fcinfo` must be a bm25query.
+unsafe fn score(fcinfo: pgrx::pg_sys::FunctionCallInfo, document: TsVectorInput) -> f64 {
+    let prepared = unsafe { Prepared::get(fcinfo, 1) };
+    let relation = Index::open_bm25(prepared.index);
+    let index = unsafe { PostgresRelation::new(relation.raw()) };
+    let document = cast_tsvector_to_document(
+        &prepared.seed,
+        document.as_borrowed(),
+        &prepared.multipliers,
+    );
+    bm25::evaluate(&index, &document, &prepared.query).to_f64()
+}
+
+/// # Safety
+///
+/// The second argument of `fcinfo` must be a bm25query.
+unsafe fn normalized_score(fcinfo: pgrx::pg_sys::FunctionCallInfo, document: TsVectorInput) -> f64 {
+    let prepared = unsafe { Prepared::get(fcinfo, 1) };
+    let relation = Index::open_bm25(prepared.index);
+    let index = unsafe { PostgresRelation::new(relation.raw()) };
+    let document = cast_tsvector_to_document(
+        &prepared.seed,
+        document.as_borrowed(),
+        &prepared.multipliers,
+    );
+    let score = bm25::evaluate(&index, &document, &prepared.query).to_f64();
+    if score == 0.0 {
+        return 0.0;
+    }
+    let upper_bound = prepared
+        .upper_bound
+        .get_or_init(|| bm25::upper_bound(&index, &prepared.query));
+    (score / upper_bound).min(1.0)
+}
+
+/// A bm25query with what is derived from it and its index, which is cached
+/// in `fn_extra` so that a query is expanded once per statement instead of
+/// once per row. Set-returning functions keep their own state in `fn_extra`,
+/// so they cannot use it.
+pub struct Prepared {
+    datum: Vec<u8>,
+    pub index: Oid,
+    pub seed: [u8; 32],
+    pub multipliers: [u32; 4],
+    /// The expanded query.
+    pub query: Query,
+    /// Lexemes of the query and its expansions by their keys.
+    pub lexemes: HashMap<[u8; WIDTH], Vec<u8>>,
+    /// The maximum score of the query, computed on first use.
+    upper_bound: OnceCell<f64>,
+}
+
+impl Prepared {
+    /// Returns the prepared bm25query of the argument `n`, which is prepared
+    /// again only if it differs from that of the last call.
+    ///
+    /// # Safety
+    ///
+    /// The argument `n` of `fcinfo` must be a bm25query.
+    pub unsafe fn get<'a>(fcinfo: pgrx::pg_sys::FunctionCallInfo, n: usize) -> &'a Self {
+        let datum = unsafe {
+            let raw = pgrx::fcinfo::pg_getarg_datum_raw(fcinfo, n);
+            let varlena = pgrx::pg_sys::pg_detoast_datum_packed(raw.cast_mut_ptr());
+            pgrx::varlena::varlena_to_byte_slice(varlena)
+        };
+        let mut cache = unsafe { pgrx::fcinfo::pg_func_extra(fcinfo, || None::<Self>) };
+        if (*cache)
+            .as_ref()
+            .is_none_or(|prepared| prepared.datum != datum)
+        {
+            let tuple =
+                unsafe { pgrx::fcinfo::pg_getarg::<pgrx::composite_type!("bm25query")>(fcinfo, n) }
+                    .expect("bm25query must not be null");
+            *cache = Some(Self::new(datum.to_vec(), &Bm25Query::from_tuple(&tuple)));
+        }
+        let cache = cache.into_pg();
+        unsafe { (*cache).as_ref().expect("the cache is filled") }
     }
-    if pg_class.relam() != pg_am.oid() {
-        pgrx::error!("the index {:?} is not a bm25 index", pg_class.relname());
+    fn new(datum: Vec<u8>, bm25query: &Bm25Query) -> Self {
+        let relation = Index::open_bm25(bm25query.index);
+        let index = unsafe { PostgresRelation::new(relation.raw()) };
+        let seed = bm25::seed::seed(&index);
+        let multipliers = bm25::seed::multipliers(&index);
+        let query = bm25::expand(&index, cast_bm25query_to_query(&seed, bm25query));
+        let mut lexemes = bm25query.lexemes(&seed);
+        lexemes.extend(query.expansions().iter().cloned());
+        Self {
+            datum,
+            index: bm25query.index,
+            seed,
+            multipliers,
+            query,
+            lexemes,
+            upper_bound: OnceCell::new(),
+        }
     }
-    let relation = Index::open(index, pgrx::pg_sys::AccessShareLock as _);
+}
+
+/// Returns a row for each term of the query with its share of the score, and
+/// a row with a null lexeme for the proximity component if there is one.
+#[pgrx::pg_extern(sql = "", stable, strict, parallel_safe)]
+fn _bm25_explain(
+    document: TsVectorInput,
+    query: pgrx::composite_type!("bm25query"),
+) -> TableIterator<
+    'static,
+    (
+        name!(lexeme, Option<String>),
+        name!(matched, bool),
+        name!(weight, Option<f64>),
+        name!(indexed, Option<bool>),
+        name!(document_frequency, Option<i64>),
+        name!(idf, Option<f64>),
+        name!(term_frequency, Option<f64>),
+        name!(fieldnorm, i32),
+        name!(length, f64),
+        name!(avgdl, f64),
+        name!(score, f64),
+    ),
+> {
+    let bm25query = Bm25Query::from_tuple(&query);
+    let relation = Index::open_bm25(bm25query.index);
     let index = unsafe { PostgresRelation::new(relation.raw()) };
     let seed = bm25::seed::seed(&index);
-    let lhs = cast_tsvector_to_document(&seed, lhs.as_borrowed());
-    let rhs = cast_tsvector_to_query(&seed, vector.as_borrowed());
-    let score = bm25::evaluate(&index, &lhs, &rhs);
-    -score.to_f64()
+    let multipliers = bm25::seed::multipliers(&index);
+    let mut lexemes = bm25query.lexemes(&seed);
+    let document = cast_tsvector_to_document(&seed, document.as_borrowed(), &multipliers);
+    let query = bm25::expand(&index, cast_bm25query_to_query(&seed, &bm25query));
+    lexemes.extend(query.expansions().iter().cloned());
+    let explanation = bm25::explain(&index, &document, &query);
+    let matched = explanation.matched;
+    let fieldnorm = explanation.fieldnorm as i32;
+    let length = explanation.length;
+    let avgdl = explanation.avgdl;
+    let mut rows = Vec::new();
+    for term in explanation.terms {
+        let lexeme = lexemes
+            .get(&term.key)
+            .map(|lexeme| String::from_utf8_lossy(lexeme).into_owned());
+        rows.push((
+            lexeme,
+            matched,
+            Some(term.weight),
+            Some(term.document_frequency != 0),
+            Some(term.document_frequency as i64),
+            Some(term.idf),
+            Some(term.term_frequency),
+            fieldnorm,
+            length,
+            avgdl,
+            term.score,
+        ));
+    }
+    if query.proximity() != 0.0 {
+        rows.push((
+            None,
+            matched,
+            Some(query.proximity()),
+            None,
+            None,
+            None,
+            None,
+            fieldnorm,
+            length,
+            avgdl,
+            explanation.proximity,
+        ));
+    }
+    TableIterator::new(rows)
 }
 
-struct Index {
+pub struct Index {
     raw: *mut pgrx::pg_sys::RelationData,
     lockmode: pgrx::pg_sys::LOCKMODE,
 }
 
 impl Index {
+    pub fn open_bm25(indexrelid: Oid) -> Self {
+        Self::open_bm25_with(indexrelid, pgrx::pg_sys::AccessShareLock as _)
+    }
+    pub fn open_bm25_with(indexrelid: Oid, lockmode: pgrx::pg_sys::LOCKMASK) -> Self {
+        let pg_am = PgAm::search_amname(c"bm25").unwrap();
+        let Some(pg_am) = pg_am.get() else {
+            pgrx::error!("vchord_bm25 is not installed");
+        };
+        let pg_class = PgClass::search_reloid(indexrelid).unwrap();
+        let Some(pg_class) = pg_class.get() else {
+            pgrx::error!("the relation does not exist");
+        };
+        if pg_class.relkind() != PgClassRelkind::Index {
+            pgrx::error!("the relation {:?} is not an index", pg_class.relname());
+        }
+        if pg_class.relam() != pg_am.oid() {
+            pgrx::error!("the index {:?} is not a bm25 index", pg_class.relname());
+        }
+        Self::open(indexrelid, lockmode)
+    }
     fn open(indexrelid: Oid, lockmode: pgrx::pg_sys::LOCKMASK) -> Self {
         Self {
             raw: unsafe { pgrx::pg_sys::index_open(indexrelid, lockmode) },
             lockmode,
         }
     }
-    fn raw(&self) -> *mut pgrx::pg_sys::RelationData {
+    pub fn raw(&self) -> *mut pgrx::pg_sys::RelationData {
         self.raw
     }
 }
@@ -37,6 +37,6 @@ pub trait SearchBuilder: 'static {
         fetcher: impl Fetcher + 'b,
     ) -> Box<dyn Iterator<Item = (f64, [u16; 3])> + 'b>
     where
-        R: RelationRead,
+        R: RelationRead + 'b,
         R::Page: Page<Opaque = Self::Opaque>;
 }
@@ -13,7 +13,7 @@
 // Copyright (c) 2025-2026 TensorChord Inc.
 
 use index::relation::{
-    Opaque, Page, PageGuard, Relation, RelationId, RelationPrefetch, RelationRead,
+    Opaque, Page, PageGuard, Relation, RelationId, RelationLength, RelationPrefetch, RelationRead,
     RelationReadTypes, RelationWrite, RelationWriteTypes,
 };
 use std::marker::PhantomData;
@@ -325,6 +325,37 @@ impl<O: Opaque> RelationWrite for PostgresRelation<O> {
             }
         }
     }
+    fn try_write(&self, id: u32) -> Option<PostgresBufferWriteGuard<O>> {
+        assert!(id != u32::MAX, "no such page");
+        unsafe {
+            use pgrx::pg_sys::{
+                ConditionalLockBuffer, ForkNumber, GenericXLogRegisterBuffer, GenericXLogStart,
+                ReadBufferExtended, ReadBufferMode, ReleaseBuffer,
+            };
+            let buf = ReadBufferExtended(
+                self.raw,
+                ForkNumber::MAIN_FORKNUM,
+                id,
+                ReadBufferMode::RBM_NORMAL,
+                std::ptr::null_mut(),
+            );
+            if !ConditionalLockBuffer(buf) {
+                ReleaseBuffer(buf);
+                return None;
+            }
+            let state = GenericXLogStart(self.raw);
+            let page = NonNull::new(
+                GenericXLogRegisterBuffer(state, buf, 0).cast::<MaybeUninit<PostgresPage<O>>>(),
+            )
+            .expect("failed to get page");
+            Some(PostgresBufferWriteGuard {
+                buf,
+                page: page.cast(),
+                state,
+                id,
+            })
+        }
+    }
     fn alloc(&self, opaque: <Self::Page as Page>::Opaque) -> PostgresBufferWriteGuard<O> {
         unsafe {
             use pgrx::pg_sys::{
@@ -421,6 +452,15 @@ impl<O: Opaque> RelationWrite for PostgresRelation<O> {
     }
 }
 
+impl<O: Opaque> RelationLength for PostgresRelation<O> {
+    fn len(&self) -> u32 {
+        unsafe {
+            use pgrx::pg_sys::{ForkNumber, RelationGetNumberOfBlocksInFork};
+            RelationGetNumberOfBlocksInFork(self.raw, ForkNumber::MAIN_FORKNUM)
+        }
+    }
+}
+
 impl<O: Opaque> RelationPrefetch for PostgresRelation<O> {
     fn prefetch(&self, id: u32) {
         assert!(id != u32::MAX, "no such page");
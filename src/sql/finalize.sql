@@ -2,9 +2,30 @@
 
 CREATE TYPE bm25query AS (
     vector tsvector,
-    index regclass
+    index regclass,
+    must tsvector,
+    must_not tsvector,
+    minimum_should_match text,
+    weights real[],
+    phrase tsquery,
+    slop integer,
+    proximity real,
+    k1 real,
+    b real,
+    prefixes text[],
+    max_expansions integer,
+    fuzziness integer,
+    fuzzy_discount real
 );
 
+-- List of internal functions
+
+CREATE FUNCTION _bm25_evaluate(tsvector, bm25query) RETURNS double precision
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_evaluate_wrapper';
+
+CREATE FUNCTION _bm25_evaluate_normalized(tsvector, bm25query) RETURNS double precision
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_evaluate_normalized_wrapper';
+
 -- List of operators
 
 CREATE OPERATOR <&> (
@@ -13,13 +34,49 @@ CREATE OPERATOR <&> (
     RIGHTARG = bm25query
 );
 
+CREATE OPERATOR <&&> (
+    PROCEDURE = _bm25_evaluate_normalized,
+    LEFTARG = tsvector,
+    RIGHTARG = bm25query
+);
+
 -- List of functions
 
 CREATE FUNCTION bm25_amhandler(internal) RETURNS index_am_handler
 IMMUTABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_amhandler_wrapper';
 
-CREATE FUNCTION to_bm25query(tsvector, regclass) RETURNS bm25query
-IMMUTABLE PARALLEL SAFE LANGUAGE sql AS 'SELECT ROW($1, $2)::bm25query';
+CREATE FUNCTION bm25_search(index regclass, query tsvector, k integer) RETURNS TABLE (ctid tid, score double precision, terms text[])
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_search_wrapper';
+
+CREATE FUNCTION bm25_vocabulary(index regclass) RETURNS TABLE (term text, document_frequency bigint)
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_vocabulary_wrapper';
+
+CREATE FUNCTION bm25_index_stats(index regclass) RETURNS TABLE (number_of_segments bigint, number_of_documents bigint, number_of_deleted_documents bigint, number_of_unsealed_documents bigint, sum_of_document_lengths double precision, avgdl double precision, vectors_pages bigint, segments_pages bigint, terms_pages bigint, documents_pages bigint, tokens_pages bigint, summaries_pages bigint, blocks_pages bigint, positions_pages bigint, addresses_pages bigint)
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_index_stats_wrapper';
+
+CREATE FUNCTION bm25_term_stats(index regclass, term text) RETURNS TABLE (document_frequency bigint, sum_of_term_frequencies double precision, idf double precision, max_term_frequency double precision, number_of_blocks bigint)
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_term_stats_wrapper';
+
+CREATE FUNCTION bm25_verify(index regclass, heapallindexed boolean DEFAULT false) RETURNS TABLE (region text, page bigint, problem text)
+STRICT LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_verify_wrapper';
+
+CREATE FUNCTION bm25_score(document tsvector, query bm25query) RETURNS double precision
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_score_wrapper';
+
+CREATE FUNCTION bm25_normalized_score(document tsvector, query bm25query) RETURNS double precision
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_normalized_score_wrapper';
+
+CREATE FUNCTION bm25_explain(document tsvector, query bm25query) RETURNS TABLE (lexeme text, matched boolean, weight double precision, indexed boolean, document_frequency bigint, idf double precision, term_frequency double precision, fieldnorm integer, length double precision, avgdl double precision, score double precision)
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_explain_wrapper';
+
+CREATE FUNCTION bm25_fuse(query bm25query, ids tid[], scores double precision[] DEFAULT NULL, method text DEFAULT 'rrf', k integer DEFAULT 10, rrf_k integer DEFAULT 60, weight double precision DEFAULT 0.5, higher_is_better boolean DEFAULT true) RETURNS TABLE (ctid tid, score double precision, bm25_rank integer, other_rank integer)
+STABLE PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_fuse_wrapper';
+
+CREATE FUNCTION bm25_highlight(document text, config regconfig, query bm25query, options text DEFAULT '') RETURNS text
+STABLE STRICT PARALLEL SAFE LANGUAGE c AS 'MODULE_PATHNAME', '_bm25_highlight_wrapper';
+
+CREATE FUNCTION to_bm25query(vector tsvector, index regclass, must tsvector DEFAULT NULL, must_not tsvector DEFAULT NULL, minimum_should_match text DEFAULT NULL, weights real[] DEFAULT NULL, phrase tsquery DEFAULT NULL, slop integer DEFAULT NULL, proximity real DEFAULT NULL, k1 real DEFAULT NULL, b real DEFAULT NULL, prefixes text[] DEFAULT NULL, max_expansions integer DEFAULT NULL, fuzziness integer DEFAULT NULL, fuzzy_discount real DEFAULT NULL) RETURNS bm25query
+IMMUTABLE PARALLEL SAFE LANGUAGE sql AS 'SELECT ROW($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)::bm25query';
 
 -- List of access methods
 
@@ -32,4 +89,6 @@ CREATE OPERATOR FAMILY bm25_ops USING bm25;
 -- List of operator classes
 
 CREATE OPERATOR CLASS bm25_ops FOR TYPE tsvector USING bm25 FAMILY bm25_ops AS
-    OPERATOR 1 <&>(tsvector, bm25query) FOR ORDER BY float_ops;
+    OPERATOR 1 <&>(tsvector, bm25query) FOR ORDER BY float_ops,
+    OPERATOR 2 @@(tsvector, tsquery),
+    OPERATOR 3 <&&>(tsvector, bm25query) FOR ORDER BY float_ops;